@@ -0,0 +1,109 @@
+//! Bit-level reading and writing, most significant bit first.
+//!
+//! The first bit written lands in the top bit of the first byte, the same order `au0` packs
+//! its booleans in. A field may straddle any number of byte boundaries, and the final byte
+//! is padded with zero bits.
+
+/// Packs fields of any width from 1 to 64 bits into bytes.
+///
+/// # Example
+///
+/// ```
+/// use vsf::bitio::{BitReader, BitWriter};
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let fields = [(0b101, 3), (0x1F3, 9), (1, 1), (0x2AAAA, 18), (0x5, 5)];
+/// let mut writer = BitWriter::new();
+/// for (value, width) in fields {
+///     writer.write_bits(value, width);
+/// }
+/// assert_eq!(writer.bit_len(), 36);
+/// let bytes = writer.into_bytes();
+/// assert_eq!(bytes.len(), 5);
+/// assert_eq!(bytes[0], 0b1011_1111);
+///
+/// let mut reader = BitReader::new(&bytes);
+/// for (value, width) in fields {
+///     assert_eq!(reader.read_bits(width)?, value);
+/// }
+/// assert_eq!(reader.read_bits(4)?, 0); // Padding
+/// assert!(reader.read_bits(1).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    bit_len: usize,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        BitWriter::default()
+    }
+
+    /// Appends the low `width` bits of `value`, highest of them first.
+    ///
+    /// Panics if `width` is over 64.
+    pub fn write_bits(&mut self, value: u64, width: u32) {
+        assert!(width <= 64, "Cannot write more than 64 bits at once!");
+        for bit in (0..width).rev() {
+            if self.bit_len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if value >> bit & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.bit_len % 8);
+            }
+            self.bit_len += 1;
+        }
+    }
+
+    /// Number of bits written so far, padding excluded.
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    /// Returns the packed bytes, the last one padded with zero bits.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Reads back fields packed by [`BitWriter`].
+#[derive(Debug)]
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize, // Bits consumed so far
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        BitReader { data, position: 0 }
+    }
+
+    /// Reads the next `width` bits as an unsigned value.
+    ///
+    /// Fails with `UnexpectedEof`, consuming nothing, when fewer than `width` bits remain.
+    /// Panics if `width` is over 64.
+    pub fn read_bits(&mut self, width: u32) -> Result<u64, std::io::Error> {
+        assert!(width <= 64, "Cannot read more than 64 bits at once!");
+        if self.data.len() * 8 - self.position < width as usize {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Not enough bits left!",
+            ));
+        }
+        let mut value = 0u64;
+        for _ in 0..width {
+            let bit = self.data[self.position / 8] >> (7 - self.position % 8) & 1;
+            value = value << 1 | bit as u64;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    /// Number of bits consumed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
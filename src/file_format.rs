@@ -0,0 +1,1666 @@
+//! VSF file layout: the `RÅ<` header, its label definitions, and the sections they point to.
+//!
+//! A file is laid out as:
+//!
+//! ```text
+//! RÅ< b z y c [l x ...] ( d o b c ) ( d o b c ) ... > section section ...
+//! ```
+//!
+//! The header opens with the magic bytes, the header length (written inclusively, so it
+//! counts its own bytes), the version and backward version, and the number of label definitions, optionally followed by labelled metadata
+//! such as the source file name (`l` name, `x` value). Each label definition names a section
+//! (`d`) and records where it lives (`o`, offset in bits from the start of the file) and
+//! how big it is (`b`, length in bits), optionally followed by an element count (`c`).
+//! Section bodies follow the closing `>`.
+
+use crate::vsf::{decode_usize_inclusive, parse, EncodeNumber, VsfType};
+
+/// Magic bytes that open every VSF file ("RÅ<").
+pub const MAGIC: &[u8] = b"R\xC3\x85<";
+/// Format version written by this library.
+pub const VSF_VERSION: usize = 1;
+/// Oldest format version this library can still read.
+pub const VSF_BACKWARD_COMPAT: usize = 1;
+
+/// A single `( d o b c )` entry in the header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelDefinition {
+    pub label: String,
+    pub offset: usize,        // Offset in bits from the start of the file
+    pub length: usize,        // Length in bits
+    pub count: Option<usize>, // Element count, if the writer recorded one
+}
+
+impl LabelDefinition {
+    /// Offset of the section in bytes from the start of the file.
+    ///
+    /// The header stores `o` and `b` in bits; sections written by this library always start
+    /// and end on a byte boundary, so these divide by 8 (rounding down otherwise).
+    ///
+    /// ```
+    /// use vsf::file_format::{parse_header, VsfBuilder};
+    ///
+    /// let file = VsfBuilder::new()
+    ///     .unboxed_section("first", vec![0; 10])
+    ///     .unboxed_section("second", vec![1; 3])
+    ///     .build()
+    ///     .unwrap();
+    /// let header = parse_header(&file).unwrap();
+    /// let second = &header.labels[1];
+    /// assert_eq!(second.offset_bytes(), header.length / 8 + 10);
+    /// assert_eq!(second.offset, second.offset_bytes() * 8);
+    /// assert_eq!(second.length_bytes(), 3);
+    /// assert_eq!(&file[second.offset_bytes()..][..second.length_bytes()], &[1, 1, 1]);
+    /// ```
+    pub fn offset_bytes(&self) -> usize {
+        self.offset / 8
+    }
+
+    /// Length of the section in bytes.
+    pub fn length_bytes(&self) -> usize {
+        self.length / 8
+    }
+}
+
+/// The parsed `RÅ< ... >` header of a VSF file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VsfHeader {
+    pub version: usize,          // z: version the file was written with
+    pub backward_version: usize, // y: oldest reader version that can read the file
+    pub length: usize,           // Header length in bits, magic through '>'
+    pub labels: Vec<LabelDefinition>,
+    pub source_name: Option<String>, // Name of the file the data came from
+    pub content_type: Option<String>, // MIME-like hint of what the file holds
+    pub crc: bool,                   // Whether every section body is followed by a u5 CRC32
+}
+
+/// Assembles a complete VSF file: header first, then each section body in the order added.
+#[derive(Debug, Default)]
+pub struct VsfBuilder {
+    sections: Vec<(String, SectionBody)>,
+    crc: bool,
+    dedup: bool,
+    source_name: Option<String>,
+    content_type: Option<String>,
+}
+
+/// Where a section's bytes come from when the file is built.
+enum SectionBody {
+    Bytes(Vec<u8>),
+    Reader(Box<dyn std::io::Read>, usize), // Read only while building, length in bytes
+}
+
+impl SectionBody {
+    fn len(&self) -> usize {
+        match self {
+            SectionBody::Bytes(bytes) => bytes.len(),
+            SectionBody::Reader(_, length) => *length,
+        }
+    }
+}
+
+impl std::fmt::Debug for SectionBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SectionBody::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+            SectionBody::Reader(_, length) => write!(f, "Reader({} bytes)", length),
+        }
+    }
+}
+
+impl VsfBuilder {
+    pub fn new() -> Self {
+        VsfBuilder::default()
+    }
+
+    /// Adds a section whose body is the raw blob, with no VSF type framing around it.
+    ///
+    /// The header records the blob's offset and length, so a reader can slice (or map) the
+    /// bytes directly without parsing anything.
+    ///
+    /// ```
+    /// use vsf::file_format::{read_section, VsfBuilder};
+    ///
+    /// let elevation: Vec<u8> = (0..4096u32).map(|i| (i * 7) as u8).collect();
+    /// let file = VsfBuilder::new()
+    ///     .unboxed_section("elevation_raster", elevation.clone())
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(read_section(&file, "elevation_raster").unwrap(), &elevation[..]);
+    /// ```
+    pub fn unboxed_section(mut self, label: &str, blob: Vec<u8>) -> Self {
+        self.sections
+            .push((label.to_owned(), SectionBody::Bytes(blob)));
+        self
+    }
+
+    /// Adds a `thumbnail` section that is always written first, right after the header, no
+    /// matter when it is added. A reader can then fetch it with one small read from the start
+    /// of the file, before any of the large sections.
+    ///
+    /// ```
+    /// use vsf::file_format::{parse_header, read_section, VsfBuilder};
+    ///
+    /// let file = VsfBuilder::new()
+    ///     .unboxed_section("image", vec![0; 1 << 16])
+    ///     .thumbnail(vec![7; 48])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let header = parse_header(&file).unwrap();
+    /// let first = header.labels.iter().min_by_key(|definition| definition.offset).unwrap();
+    /// assert_eq!(first.label, "thumbnail");
+    /// assert_eq!(first.offset, header.length);
+    /// assert_eq!(read_section(&file, "thumbnail").unwrap(), &[7u8; 48][..]);
+    /// ```
+    pub fn thumbnail(mut self, blob: Vec<u8>) -> Self {
+        self.sections
+            .insert(0, ("thumbnail".to_owned(), SectionBody::Bytes(blob)));
+        self
+    }
+
+    /// Adds a section holding `value`, preceded by a `d` value naming what the data means
+    /// (`"elevation_meters"`, `"thumbnail_rgb"`), which [`section_dtype`] reads back.
+    ///
+    /// ```
+    /// use vsf::file_format::{read_section, section_dtype, VsfBuilder};
+    /// use vsf::vsf::{parse, VsfType};
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let elevation = VsfType::af5(vec![1204.5, 1210.0, 1198.25]);
+    /// let file = VsfBuilder::new()
+    ///     .data_with_dtype("terrain", "elevation_meters", &elevation)?
+    ///     .unboxed_section("notes", b"raw".to_vec())
+    ///     .build()?;
+    ///
+    /// assert_eq!(section_dtype(&file, "terrain")?.as_deref(), Some("elevation_meters"));
+    /// assert_eq!(section_dtype(&file, "notes")?, None);
+    ///
+    /// let section = read_section(&file, "terrain")?;
+    /// let mut pointer = 0;
+    /// parse(section, &mut pointer)?;
+    /// assert_eq!(parse(section, &mut pointer)?, elevation);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn data_with_dtype(
+        self,
+        label: &str,
+        dtype: &str,
+        value: &VsfType,
+    ) -> Result<Self, std::io::Error> {
+        let mut body = VsfType::d(dtype.to_owned()).flatten()?;
+        body.append(&mut value.flatten()?);
+        Ok(self.unboxed_section(label, body))
+    }
+
+    /// Adds a section whose `length` bytes are read from `reader` only when the file is built,
+    /// and copied straight to the output a buffer at a time.
+    ///
+    /// Use this with [`VsfBuilder::build_to`] for sections too large to hold in memory. The
+    /// reader must yield at least `length` bytes; building fails if it runs out sooner. These
+    /// sections are never deduplicated, as that would mean reading them twice.
+    ///
+    /// ```
+    /// use std::io::{Cursor, Read};
+    /// use vsf::file_format::{parse_header, read_section, VsfBuilder};
+    ///
+    /// // 16 MiB of samples, generated as they are read and never held in memory
+    /// let samples = std::io::repeat(0x5A).take(16 << 20);
+    /// let mut cursor = Cursor::new(Vec::new());
+    /// VsfBuilder::new()
+    ///     .with_crc(true)
+    ///     .unboxed_section("thumbnail", vec![1, 2, 3])
+    ///     .streamed_section("samples", samples, 16 << 20)
+    ///     .build_to(&mut cursor)
+    ///     .unwrap();
+    ///
+    /// let file = cursor.into_inner();
+    /// let samples = read_section(&file, "samples").unwrap();
+    /// assert_eq!(samples.len(), 16 << 20);
+    /// assert!(samples.iter().all(|sample| *sample == 0x5A));
+    /// assert!(parse_header(&file).unwrap().verify_crc(&file).is_ok());
+    ///
+    /// // A reader that runs out early is an error, not a short section
+    /// let short = VsfBuilder::new()
+    ///     .streamed_section("samples", std::io::repeat(0).take(10), 11)
+    ///     .build();
+    /// assert_eq!(short.unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+    /// ```
+    pub fn streamed_section<R: std::io::Read + 'static>(
+        mut self,
+        label: &str,
+        reader: R,
+        length: usize,
+    ) -> Self {
+        self.sections.push((
+            label.to_owned(),
+            SectionBody::Reader(Box::new(reader), length),
+        ));
+        self
+    }
+
+    /// Follows every section body with a `u5` CRC32 of that body, for cheap corruption checks
+    /// with [`VsfHeader::verify_crc`]. The CRC sits outside the section's recorded length, so
+    /// readers that don't care about it never see it. The header records the choice, so
+    /// [`append_section`] keeps writing CRCs for the sections it adds.
+    ///
+    /// ```
+    /// use vsf::file_format::{parse_header, read_section, VsfBuilder};
+    ///
+    /// let mut file = VsfBuilder::new()
+    ///     .with_crc(true)
+    ///     .unboxed_section("config", b"baud=115200".to_vec())
+    ///     .unboxed_section("calibration", vec![3, 1, 4, 1, 5])
+    ///     .build()
+    ///     .unwrap();
+    /// let header = parse_header(&file).unwrap();
+    /// assert!(header.verify_crc(&file).is_ok());
+    ///
+    /// let offset = header.labels[1].offset_bytes();
+    /// file[offset + 2] ^= 0x10;
+    /// assert!(header.verify_crc(&file).is_err());
+    /// assert_eq!(read_section(&file, "config").unwrap(), b"baud=115200");
+    /// ```
+    pub fn with_crc(mut self, enabled: bool) -> Self {
+        self.crc = enabled;
+        self
+    }
+
+    /// Records the name of the file the data came from in the header.
+    ///
+    /// ```
+    /// use vsf::file_format::{parse_header, VsfBuilder};
+    ///
+    /// let file = VsfBuilder::new()
+    ///     .source_name("スキャン_0042.dcm")
+    ///     .content_type("application/dicom")
+    ///     .unboxed_section("pixels", vec![0; 64])
+    ///     .build()
+    ///     .unwrap();
+    /// let header = parse_header(&file).unwrap();
+    /// assert_eq!(header.source_name(), Some("スキャン_0042.dcm"));
+    /// assert_eq!(header.content_type(), Some("application/dicom"));
+    /// ```
+    pub fn source_name(mut self, name: &str) -> Self {
+        self.source_name = Some(name.to_owned());
+        self
+    }
+
+    /// Records a MIME-like hint of what the file holds (`image/x-lumis-raw`,
+    /// `application/dicom`) in the header, so tools can route it without reading the sections.
+    pub fn content_type(mut self, content_type: &str) -> Self {
+        self.content_type = Some(content_type.to_owned());
+        self
+    }
+
+    /// Stores identical section bodies only once. Each label still gets its own header entry,
+    /// but sections whose bytes match an earlier section point at that section's offset
+    /// instead of repeating the body.
+    ///
+    /// ```
+    /// use vsf::file_format::{read_section, VsfBuilder};
+    ///
+    /// let logo: Vec<u8> = (0..1024u32).map(|i| (i % 253) as u8).collect();
+    /// let builder = |dedup| {
+    ///     VsfBuilder::new()
+    ///         .dedup_sections(dedup)
+    ///         .unboxed_section("cover_logo", logo.clone())
+    ///         .unboxed_section("footer_logo", logo.clone())
+    ///         .build()
+    ///         .unwrap()
+    /// };
+    /// let (plain, deduped) = (builder(false), builder(true));
+    /// assert_eq!(plain.len() - deduped.len(), 1024);
+    /// assert_eq!(read_section(&deduped, "cover_logo").unwrap(), &logo[..]);
+    /// assert_eq!(read_section(&deduped, "footer_logo").unwrap(), &logo[..]);
+    /// ```
+    pub fn dedup_sections(mut self, enabled: bool) -> Self {
+        self.dedup = enabled;
+        self
+    }
+
+    /// Builds the file with an Ed25519 signature over the header and every section, stored as
+    /// a `g` value in a final [`SIGNATURE_LABEL`](crate::verification::SIGNATURE_LABEL)
+    /// section.
+    ///
+    /// The signature covers the version, metadata and section count, then each section's
+    /// label, offset past the header, length and body in label order. Changing the metadata,
+    /// moving a body or pointing a label at other bytes all break it. Check it with
+    /// [`verify_file`](crate::verification::verify_file).
+    ///
+    /// ```
+    /// use vsf::file_format::{parse_header, read_section, VsfBuilder};
+    /// use vsf::verification::verify_file;
+    ///
+    /// let secret_key = [42u8; 32];
+    /// let public_key = ed25519_dalek::SigningKey::from_bytes(&secret_key)
+    ///     .verifying_key()
+    ///     .to_bytes();
+    /// let file = VsfBuilder::new()
+    ///     .content_type("application/dicom")
+    ///     .unboxed_section("left", vec![1; 16])
+    ///     .unboxed_section("right", vec![2; 16])
+    ///     .sign(&secret_key)
+    ///     .unwrap();
+    /// assert!(verify_file(&file, &public_key).unwrap());
+    /// let signature = read_section(&file, "signature").unwrap().to_vec();
+    ///
+    /// // Re-ordering the sections after signing breaks the signature
+    /// let reordered = VsfBuilder::new()
+    ///     .content_type("application/dicom")
+    ///     .unboxed_section("right", vec![2; 16])
+    ///     .unboxed_section("left", vec![1; 16])
+    ///     .unboxed_section("signature", signature.clone())
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(!verify_file(&reordered, &public_key).unwrap());
+    ///
+    /// // So does changing the header metadata
+    /// let relabelled = VsfBuilder::new()
+    ///     .content_type("text/html")
+    ///     .unboxed_section("left", vec![1; 16])
+    ///     .unboxed_section("right", vec![2; 16])
+    ///     .unboxed_section("signature", signature.clone())
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(!verify_file(&relabelled, &public_key).unwrap());
+    ///
+    /// // And swapping which body each label points at
+    /// let swapped = VsfBuilder::new()
+    ///     .content_type("application/dicom")
+    ///     .unboxed_section("left", vec![2; 16])
+    ///     .unboxed_section("right", vec![1; 16])
+    ///     .unboxed_section("signature", signature)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(!verify_file(&swapped, &public_key).unwrap());
+    ///
+    /// let checked = VsfBuilder::new()
+    ///     .with_crc(true)
+    ///     .unboxed_section("left", vec![1; 16])
+    ///     .sign(&secret_key)
+    ///     .unwrap();
+    /// assert!(verify_file(&checked, &public_key).unwrap());
+    /// assert!(parse_header(&checked).unwrap().verify_crc(&checked).is_ok());
+    /// ```
+    pub fn sign(self, secret_key: &[u8; 32]) -> Result<Vec<u8>, std::io::Error> {
+        // Lay the file out with a placeholder of the signature's exact size, then sign the
+        // finished layout and write the signature over the placeholder
+        let crc = self.crc;
+        let placeholder = VsfType::g(vec![0; 64]).flatten()?;
+        let mut file = self
+            .unboxed_section(crate::verification::SIGNATURE_LABEL, placeholder)
+            .build()?;
+        let header = parse_header(&file)?;
+        let definition = header
+            .labels
+            .iter()
+            .find(|definition| definition.label == crate::verification::SIGNATURE_LABEL)
+            .filter(|signature| {
+                header.labels.iter().all(|definition| {
+                    definition.label == signature.label || definition.offset != signature.offset
+                })
+            })
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "A section duplicates the signature placeholder!",
+                )
+            })?;
+        let signed = crate::verification::canonical_file(&file, &header)?;
+        let signature = crate::verification::sign_ed25519(&signed, secret_key).flatten()?;
+        let start = definition.offset_bytes();
+        let end = start + definition.length_bytes();
+        file[start..end].copy_from_slice(&signature);
+        if crc {
+            let checksum = VsfType::u5(crc32fast::hash(&signature)).flatten()?;
+            file[end..end + CRC_LENGTH].copy_from_slice(&checksum);
+        }
+        Ok(file)
+    }
+
+    /// Validates the labels and writes the header followed by every section body.
+    pub fn build(self) -> Result<Vec<u8>, std::io::Error> {
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        self.build_to(&mut cursor)?;
+        Ok(cursor.into_inner())
+    }
+
+    /// Same as [`VsfBuilder::build`], but writes the file to `writer` a piece at a time
+    /// instead of assembling it in memory first.
+    ///
+    /// The space for the header is written as zeros, then each body is written or streamed
+    /// from its reader (see [`VsfBuilder::streamed_section`]) with its CRC computed on the way
+    /// through. Only once every body is in place does the writer seek back and fill in the
+    /// header with the offsets and lengths, so a build that fails halfway never leaves behind
+    /// something that looks like a complete file. The writer is left at the end of the file.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use vsf::file_format::VsfBuilder;
+    ///
+    /// let builder = || {
+    ///     VsfBuilder::new()
+    ///         .with_crc(true)
+    ///         .unboxed_section("thumbnail", vec![9; 64])
+    ///         .unboxed_section("image", vec![3; 100_000])
+    /// };
+    /// let mut cursor = Cursor::new(Vec::new());
+    /// builder().build_to(&mut cursor).unwrap();
+    /// assert_eq!(cursor.into_inner(), builder().build().unwrap());
+    /// ```
+    pub fn build_to<W: std::io::Write + std::io::Seek>(
+        mut self,
+        writer: &mut W,
+    ) -> Result<(), std::io::Error> {
+        let crc_bits = if self.crc { CRC_LENGTH * 8 } else { 0 };
+        let mut labels: Vec<LabelDefinition> = Vec::with_capacity(self.sections.len());
+        // Bodies actually written, and where each one starts, keyed by the BLAKE3 hash of its bytes
+        let mut written: Vec<bool> = Vec::with_capacity(self.sections.len());
+        let mut stored: std::collections::HashMap<[u8; 32], Vec<(usize, usize)>> =
+            std::collections::HashMap::new();
+        let mut offset = 0;
+        for (index, (label, body)) in self.sections.iter().enumerate() {
+            validate_name(label)?;
+            if labels.iter().any(|definition| &definition.label == label) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("Section '{}' already exists!", label),
+                ));
+            }
+            if let (true, SectionBody::Bytes(bytes)) = (self.dedup, body) {
+                let candidates = stored.entry(*blake3::hash(bytes).as_bytes()).or_default();
+                let duplicate =
+                    candidates
+                        .iter()
+                        .find(|(earlier, _)| match &self.sections[*earlier].1 {
+                            SectionBody::Bytes(earlier) => earlier == bytes,
+                            SectionBody::Reader(..) => false,
+                        });
+                if let Some((_, earlier_offset)) = duplicate {
+                    labels.push(LabelDefinition {
+                        label: label.clone(),
+                        offset: *earlier_offset,
+                        length: bytes.len() * 8,
+                        count: None,
+                    });
+                    written.push(false);
+                    continue;
+                }
+                candidates.push((index, offset));
+            }
+            labels.push(LabelDefinition {
+                label: label.clone(),
+                offset,
+                length: body.len() * 8,
+                count: None,
+            });
+            written.push(true);
+            offset += body.len() * 8 + crc_bits;
+        }
+
+        let header = write_header(&VsfHeader {
+            version: VSF_VERSION,
+            backward_version: VSF_BACKWARD_COMPAT,
+            length: 0,
+            labels,
+            source_name: self.source_name.clone(),
+            content_type: self.content_type.clone(),
+            crc: self.crc,
+        })?;
+        let start = writer.stream_position()?;
+        writer.write_all(&vec![0; header.len()])?;
+        for ((label, body), written) in self.sections.iter_mut().zip(written) {
+            if !written {
+                continue;
+            }
+            let crc = match body {
+                SectionBody::Bytes(bytes) => {
+                    writer.write_all(bytes)?;
+                    crc32fast::hash(bytes)
+                }
+                SectionBody::Reader(reader, length) => stream_body(label, reader, *length, writer)?,
+            };
+            if self.crc {
+                writer.write_all(&VsfType::u5(crc).flatten()?)?;
+            }
+        }
+        let end = writer.stream_position()?;
+        writer.seek(std::io::SeekFrom::Start(start))?;
+        writer.write_all(&header)?;
+        writer.seek(std::io::SeekFrom::Start(end))?;
+        Ok(())
+    }
+}
+
+/// Copies exactly `length` bytes from `reader` to `writer`, returning their CRC32.
+fn stream_body<W: std::io::Write>(
+    label: &str,
+    reader: &mut Box<dyn std::io::Read>,
+    length: usize,
+    writer: &mut W,
+) -> Result<u32, std::io::Error> {
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0u8; 65536];
+    let mut remaining = length;
+    while remaining > 0 {
+        let wanted = remaining.min(buffer.len());
+        match reader.read(&mut buffer[..wanted]) {
+            Ok(0) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    format!("Section '{}' ended {} bytes short!", label, remaining),
+                ))
+            }
+            Ok(read) => {
+                hasher.update(&buffer[..read]);
+                writer.write_all(&buffer[..read])?;
+                remaining -= read;
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(hasher.finalize())
+}
+
+/// Builds a section body of labelled fields, `[ (l:value) (l:value) ... ]`, which
+/// [`field`] reads back.
+///
+/// # Example
+///
+/// ```
+/// use vsf::file_format::{field, read_section, SectionBuilder, VsfBuilder};
+/// use vsf::vsf::VsfType;
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let capture = SectionBuilder::new()
+///     .field("iso_speed", 1600u32)
+///     .field("exposure_s", 1.0f64 / 250.0)
+///     .field("lens", "35mm f/1.4")
+///     .build()?;
+/// let file = VsfBuilder::new().unboxed_section("capture", capture).build()?;
+///
+/// let capture = read_section(&file, "capture")?;
+/// assert_eq!(field(capture, "iso_speed")?, Some(VsfType::u5(1600)));
+/// assert_eq!(field(capture, "exposure_s")?, Some(VsfType::f6(0.004)));
+/// assert_eq!(field(capture, "lens")?, Some(VsfType::x("35mm f/1.4".to_owned())));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct SectionBuilder {
+    fields: Vec<(String, VsfType)>,
+}
+
+impl SectionBuilder {
+    pub fn new() -> Self {
+        SectionBuilder::default()
+    }
+
+    /// Adds a field, converting `value` to the VSF type of matching width.
+    pub fn field<T: Into<VsfType>>(mut self, label: &str, value: T) -> Self {
+        self.fields.push((label.to_owned(), value.into()));
+        self
+    }
+
+    /// Validates the labels and writes every field in the order added.
+    pub fn build(self) -> Result<Vec<u8>, std::io::Error> {
+        let mut flat = vec![b'['];
+        for (label, value) in self.fields {
+            validate_name(&label)?;
+            flat.push(b'(');
+            flat.append(&mut VsfType::l(label).flatten()?);
+            flat.push(b':');
+            flat.append(&mut value.flatten()?);
+            flat.push(b')');
+        }
+        flat.push(b']');
+        Ok(flat)
+    }
+}
+
+/// A VSF file mapped into memory, for reading individual sections of very large files
+/// without loading the rest.
+#[cfg(feature = "mmap")]
+pub struct MappedVsf {
+    map: memmap2::Mmap,
+    header: VsfHeader,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedVsf {
+    pub fn header(&self) -> &VsfHeader {
+        &self.header
+    }
+
+    /// Returns the section named `label` as a slice of the mapping, or `None` if there is no
+    /// such section or it does not fit inside the file.
+    pub fn section(&self, label: &str) -> Option<&[u8]> {
+        let definition = self
+            .header
+            .labels
+            .iter()
+            .find(|definition| definition.label == label)?;
+        section_bytes(&self.map, definition).ok()
+    }
+}
+
+/// Maps the file at `path` and parses its header.
+///
+/// The mapping assumes the file is not modified by anyone else while it is open.
+///
+/// ```
+/// use vsf::file_format::{open_mmap, VsfBuilder};
+///
+/// let path = std::env::temp_dir().join(format!("vsf_open_mmap_{}.vsf", std::process::id()));
+/// let file = VsfBuilder::new()
+///     .unboxed_section("thumbnail", vec![1, 2, 3, 4])
+///     .unboxed_section("image", vec![0; 1 << 20])
+///     .build()
+///     .unwrap();
+/// std::fs::write(&path, &file).unwrap();
+///
+/// let mapped = open_mmap(&path).unwrap();
+/// assert_eq!(mapped.section("thumbnail"), Some(&[1u8, 2, 3, 4][..]));
+/// assert_eq!(mapped.section("image").map(|image| image.len()), Some(1 << 20));
+/// assert_eq!(mapped.section("audio"), None);
+/// drop(mapped);
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+#[cfg(feature = "mmap")]
+pub fn open_mmap(path: &std::path::Path) -> Result<MappedVsf, std::io::Error> {
+    let file = std::fs::File::open(path)?;
+    // Safety: the mapping is read-only and the caller is responsible for not truncating or
+    // rewriting the file while it is mapped
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    let header = parse_header(&map)?;
+    Ok(MappedVsf { map, header })
+}
+
+/// Bytes taken by the `u5` CRC that follows each section when checksums are enabled.
+const CRC_LENGTH: usize = 6;
+
+impl VsfHeader {
+    /// Starts a header with no sections, its `length` already set for what it holds.
+    ///
+    /// `file_type` is stored as the header's `content_type`. Build files this way only when
+    /// writing a custom container; [`VsfBuilder`] lays out the section bodies as well.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vsf::file_format::{parse_header, LabelDefinition, VsfHeader, VSF_VERSION};
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let mut header = VsfHeader::new(VSF_VERSION, 1, Some("map/tiles"))?;
+    /// header.add_section(LabelDefinition {
+    ///     label: "elevation".to_owned(),
+    ///     offset: 0,
+    ///     length: 4096 * 8,
+    ///     count: None,
+    /// })?;
+    /// header.add_section(LabelDefinition {
+    ///     label: "roads".to_owned(),
+    ///     offset: 4096 * 8,
+    ///     length: 512 * 8,
+    ///     count: Some(64),
+    /// })?;
+    ///
+    /// let flat = header.serialize()?;
+    /// assert_eq!(flat.len() * 8, header.length);
+    /// assert_eq!(header.labels[0].offset, header.length);
+    /// assert_eq!(parse_header(&flat)?, header);
+    ///
+    /// let roads = LabelDefinition {
+    ///     label: "roads".to_owned(),
+    ///     offset: 0,
+    ///     length: 8,
+    ///     count: None,
+    /// };
+    /// assert!(header.add_section(roads.clone()).is_err());
+    /// let bad_name = LabelDefinition { label: "a(".to_owned(), ..roads.clone() };
+    /// assert!(header.add_section(bad_name).is_err());
+    ///
+    /// header.labels[0].offset = 0;
+    /// let before = header.clone();
+    /// let error = header
+    ///     .add_section(LabelDefinition { label: "rivers".to_owned(), ..roads })
+    ///     .unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    /// assert_eq!(header, before);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        version: usize,
+        backward_version: usize,
+        file_type: Option<&str>,
+    ) -> Result<Self, std::io::Error> {
+        let mut header = VsfHeader {
+            version,
+            backward_version,
+            length: 0,
+            labels: Vec::new(),
+            source_name: None,
+            content_type: file_type.map(str::to_owned),
+            crc: false,
+        };
+        header.length = stabilize(&mut header)? * 8;
+        Ok(header)
+    }
+
+    /// Adds a section whose offset is relative to the end of the header.
+    ///
+    /// The header grows as sections are added, so every offset (this one included) is
+    /// recomputed to stay absolute, and `length` is updated to match.
+    ///
+    /// Fails, leaving the header unchanged, when the label is not a valid name or is already
+    /// used, or when an existing section starts inside the header.
+    pub fn add_section(&mut self, definition: LabelDefinition) -> Result<(), std::io::Error> {
+        validate_name(&definition.label)?;
+        if self
+            .labels
+            .iter()
+            .any(|existing| existing.label == definition.label)
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("Section '{}' already exists!", definition.label),
+            ));
+        }
+        let mut header = self.clone();
+        for existing in &mut header.labels {
+            existing.offset = existing.offset.checked_sub(self.length).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Section '{}' starts inside the header!", existing.label),
+                )
+            })?;
+        }
+        header.labels.push(definition);
+        header.length = stabilize(&mut header)? * 8;
+        *self = header;
+        Ok(())
+    }
+
+    /// Flattens the header, magic included, exactly as [`parse_header`] reads it back.
+    pub fn serialize(&self) -> Result<Vec<u8>, std::io::Error> {
+        flatten_header(self, self.length / 8)
+    }
+
+    /// Yields each section's label and body within `file`, in header order.
+    ///
+    /// For discovering what a file holds without knowing its labels; sections whose recorded
+    /// range does not fit in `file` are skipped (use [`read_section`] to get the error).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vsf::file_format::{parse_header, VsfBuilder};
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let file = VsfBuilder::new()
+    ///     .unboxed_section("roads", vec![1; 40])
+    ///     .unboxed_section("rivers", vec![2; 7])
+    ///     .unboxed_section("contours", vec![3; 300])
+    ///     .build()?;
+    /// let header = parse_header(&file)?;
+    ///
+    /// let sections: Vec<(&str, &[u8])> = header.sections(&file).collect();
+    /// assert_eq!(
+    ///     sections,
+    ///     [
+    ///         ("roads", &[1; 40][..]),
+    ///         ("rivers", &[2; 7][..]),
+    ///         ("contours", &[3; 300][..]),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sections<'a>(&'a self, file: &'a [u8]) -> impl Iterator<Item = (&'a str, &'a [u8])> {
+        self.labels.iter().filter_map(move |definition| {
+            section_bytes(file, definition)
+                .ok()
+                .map(|body| (definition.label.as_str(), body))
+        })
+    }
+
+    pub fn source_name(&self) -> Option<&str> {
+        self.source_name.as_deref()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Checks the CRC32 written after each section body by [`VsfBuilder::with_crc`].
+    ///
+    /// Fails with `InvalidData` when the header does not record that the file carries CRCs,
+    /// or naming the first section whose body no longer matches its checksum, or that has no
+    /// checksum after it.
+    pub fn verify_crc(&self, data: &[u8]) -> Result<(), std::io::Error> {
+        if !self.crc {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "File was written without CRCs!",
+            ));
+        }
+        for definition in &self.labels {
+            let body = section_bytes(data, definition)?;
+            let mut pointer = (definition.offset + definition.length) / 8;
+            match parse(data, &mut pointer) {
+                Ok(VsfType::u5(crc)) if crc == crc32fast::hash(body) => {}
+                Ok(VsfType::u5(_)) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("CRC mismatch in section '{}'!", definition.label),
+                    ))
+                }
+                _ => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Section '{}' has no CRC!", definition.label),
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Values defined once with an `m` marker and referred to afterwards with `r`.
+///
+/// A definition is written as `m` with the marker index, followed by the value itself. Every
+/// later occurrence of an equal value is written as just `r` with that index, so a field name
+/// repeated across thousands of records is stored once.
+///
+/// # Example
+///
+/// ```
+/// use vsf::file_format::MarkerTable;
+/// use vsf::vsf::VsfType;
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let label = VsfType::l("blood_pressure".to_owned());
+/// let mut writer = MarkerTable::new();
+/// let mut flat = Vec::new();
+/// for _ in 0..4 {
+///     flat.append(&mut writer.flatten_marked(&label)?);
+/// }
+/// assert!(flat.len() < 4 * label.flatten()?.len());
+///
+/// let mut reader = MarkerTable::new();
+/// let mut pointer = 0;
+/// while pointer < flat.len() {
+///     assert_eq!(reader.parse(&flat, &mut pointer)?, label);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MarkerTable {
+    values: Vec<VsfType>,
+}
+
+impl MarkerTable {
+    pub fn new() -> Self {
+        MarkerTable::default()
+    }
+
+    /// Returns the marker index of `value`, defining it if no equal value is defined yet.
+    ///
+    /// This only records the definition; writers should use [`MarkerTable::flatten_marked`],
+    /// which also emits the `m` definition the first time a value is seen.
+    pub fn define(&mut self, value: VsfType) -> usize {
+        match self.values.iter().position(|defined| *defined == value) {
+            Some(index) => index,
+            None => {
+                self.values.push(value);
+                self.values.len() - 1
+            }
+        }
+    }
+
+    /// Returns the value defined under marker `index`.
+    pub fn get(&self, index: usize) -> Option<&VsfType> {
+        self.values.get(index)
+    }
+
+    /// Flattens `value` as a reference if it is already defined, and as a definition
+    /// followed by the value otherwise.
+    pub fn flatten_marked(&mut self, value: &VsfType) -> Result<Vec<u8>, std::io::Error> {
+        if let Some(index) = self.values.iter().position(|defined| defined == value) {
+            return VsfType::r(index).flatten();
+        }
+        let index = self.define(value.clone());
+        let mut flat = VsfType::m(index).flatten()?;
+        flat.append(&mut value.flatten()?);
+        Ok(flat)
+    }
+
+    /// Parses the next value, recording `m` definitions and expanding `r` references to the
+    /// value they refer to.
+    pub fn parse(&mut self, data: &[u8], pointer: &mut usize) -> Result<VsfType, std::io::Error> {
+        match parse(data, pointer)? {
+            VsfType::m(index) => {
+                if index != self.values.len() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "Marker {} defined out of order, expected {}!",
+                            index,
+                            self.values.len()
+                        ),
+                    ));
+                }
+                let value = parse(data, pointer)?;
+                self.values.push(value.clone());
+                Ok(value)
+            }
+            VsfType::r(index) => self.get(index).cloned().ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Marker {} is referenced before it is defined!", index),
+                )
+            }),
+            value => Ok(value),
+        }
+    }
+
+    /// Parses every value in `data` with [`MarkerTable::parse`], so the result holds no `m`
+    /// or `r` values, only what they stand for.
+    ///
+    /// Fails with `InvalidData` on the first `r` whose marker has no earlier definition.
+    ///
+    /// ```
+    /// use vsf::file_format::MarkerTable;
+    /// use vsf::vsf::VsfType;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let mut flat = VsfType::m(0).flatten()?;
+    /// flat.append(&mut VsfType::l("unit".to_owned()).flatten()?);
+    /// flat.append(&mut VsfType::u3(12).flatten()?);
+    /// flat.append(&mut VsfType::r(0).flatten()?);
+    /// assert_eq!(
+    ///     MarkerTable::new().parse_all(&flat)?,
+    ///     [
+    ///         VsfType::l("unit".to_owned()),
+    ///         VsfType::u3(12),
+    ///         VsfType::l("unit".to_owned()),
+    ///     ]
+    /// );
+    ///
+    /// flat.append(&mut VsfType::r(3).flatten()?);
+    /// let error = MarkerTable::new().parse_all(&flat).unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    /// assert_eq!(error.to_string(), "Marker 3 is referenced before it is defined!");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse_all(&mut self, data: &[u8]) -> Result<Vec<VsfType>, std::io::Error> {
+        let mut values = Vec::new();
+        let mut pointer = 0;
+        while pointer < data.len() {
+            values.push(self.parse(data, &mut pointer)?);
+        }
+        Ok(values)
+    }
+}
+
+/// Structural bytes that may never appear in a label.
+const RESERVED_LABEL_BYTES: &[u8] = b"()<>{}";
+
+/// Checks that `name` can be used as a label without confusing a parser.
+///
+/// Labels are UTF-8 (guaranteed by `&str`) and may not contain any of the structural
+/// delimiters `( ) < > { }`.
+///
+/// # Example
+///
+/// ```
+/// use vsf::file_format::validate_name;
+///
+/// assert!(validate_name("lab_results").is_ok());
+/// assert!(validate_name("名前").is_ok());
+/// assert!(validate_name("results (final)").is_err());
+/// ```
+pub fn validate_name(name: &str) -> Result<(), std::io::Error> {
+    match name
+        .bytes()
+        .find(|byte| RESERVED_LABEL_BYTES.contains(byte))
+    {
+        Some(byte) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "Label '{}' contains the reserved character '{}'!",
+                name, byte as char
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// What [`inspect`] found about one section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionInfo {
+    pub label: String,
+    pub offset: usize,      // Offset in bytes from the start of the file
+    pub length: usize,      // Length in bytes
+    pub marker: Option<u8>, // Leading type byte of the body, None for an empty section
+}
+
+/// Shallow overview of a VSF file, as returned by [`inspect`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSummary {
+    pub version: usize,
+    pub sections: Vec<SectionInfo>,
+}
+
+/// Lists the sections of a file without decoding their bodies.
+///
+/// Only the header is parsed. Each section is located from its recorded offset and length
+/// and reports the type byte its body opens with, so large arrays are never read.
+///
+/// # Example
+///
+/// ```
+/// use vsf::file_format::{inspect, VsfBuilder};
+/// use vsf::vsf::VsfType;
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let file = VsfBuilder::new()
+///     .unboxed_section("title", VsfType::x("Survey".to_owned()).flatten()?)
+///     .unboxed_section("samples", VsfType::au4(vec![0; 100_000]).flatten()?)
+///     .build()?;
+///
+/// let summary = inspect(&file)?;
+/// assert_eq!(summary.sections.len(), 2);
+/// assert_eq!(summary.sections[0].label, "title");
+/// assert_eq!(summary.sections[0].marker, Some(b'x'));
+/// assert_eq!(summary.sections[1].marker, Some(b'a'));
+/// assert!(summary.sections[1].length > 200_000);
+/// # Ok(())
+/// # }
+/// ```
+pub fn inspect(data: &[u8]) -> Result<FileSummary, std::io::Error> {
+    let header = parse_header(data)?;
+    let mut sections = Vec::with_capacity(header.labels.len());
+    for definition in &header.labels {
+        let body = section_bytes(data, definition)?;
+        sections.push(SectionInfo {
+            label: definition.label.clone(),
+            offset: definition.offset_bytes(),
+            length: body.len(),
+            marker: body.first().copied(),
+        });
+    }
+    Ok(FileSummary {
+        version: header.version,
+        sections,
+    })
+}
+
+/// Returns the bytes of the section named `label`.
+pub fn read_section<'a>(data: &'a [u8], label: &str) -> Result<&'a [u8], std::io::Error> {
+    let header = parse_header(data)?;
+    let definition = header
+        .labels
+        .iter()
+        .find(|definition| definition.label == label)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No section labelled '{}'!", label),
+            )
+        })?;
+    section_bytes(data, definition)
+}
+
+/// Returns the value of the field named `label` in a section body.
+///
+/// Fields are written as `( l : value )` (a `d` name is accepted as well, and the `:` may be
+/// left out), optionally enclosed in `[ ]`. Values outside parentheses are stepped over, and
+/// parsing stops at the first matching field.
+///
+/// # Example
+///
+/// ```
+/// use vsf::file_format::field;
+/// use vsf::vsf::VsfType;
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let mut demographics = b"[".to_vec();
+/// for (label, value) in [
+///     ("family_name", VsfType::x("Doe".to_owned())),
+///     ("given_name", VsfType::x("Jane".to_owned())),
+///     ("height_cm", VsfType::f5(171.5)),
+/// ] {
+///     demographics.push(b'(');
+///     demographics.append(&mut VsfType::l(label.to_owned()).flatten()?);
+///     demographics.push(b':');
+///     demographics.append(&mut value.flatten()?);
+///     demographics.push(b')');
+/// }
+/// demographics.push(b']');
+///
+/// assert_eq!(
+///     field(&demographics, "given_name")?,
+///     Some(VsfType::x("Jane".to_owned()))
+/// );
+/// assert_eq!(field(&demographics, "blood_type")?, None);
+/// # Ok(())
+/// # }
+/// ```
+pub fn field(section: &[u8], label: &str) -> Result<Option<VsfType>, std::io::Error> {
+    let mut pointer = 0;
+    if section.first() == Some(&b'[') {
+        pointer += 1;
+    }
+    while pointer < section.len() {
+        match section[pointer] {
+            b']' => break,
+            b'(' => {
+                pointer += 1;
+                let name = match parse(section, &mut pointer)? {
+                    VsfType::l(name) | VsfType::d(name) => name,
+                    other => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("Field starts with {:?} instead of a label!", other),
+                        ))
+                    }
+                };
+                if section.get(pointer) == Some(&b':') {
+                    pointer += 1;
+                }
+                let value = parse(section, &mut pointer)?;
+                if section.get(pointer) != Some(&b')') {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Field '{}' is not terminated!", name),
+                    ));
+                }
+                pointer += 1;
+                if name == label {
+                    return Ok(Some(value));
+                }
+            }
+            _ => {
+                parse(section, &mut pointer)?;
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the data type written ahead of the section named `label` by
+/// [`VsfBuilder::data_with_dtype`], or `None` if the section does not open with a `d` value.
+pub fn section_dtype(data: &[u8], label: &str) -> Result<Option<String>, std::io::Error> {
+    let section = read_section(data, label)?;
+    if section.first() != Some(&b'd') {
+        return Ok(None);
+    }
+    match parse(section, &mut 0)? {
+        VsfType::d(dtype) => Ok(Some(dtype)),
+        _ => Ok(None),
+    }
+}
+
+/// Appends a new section to an existing file without touching the bodies already in it.
+///
+/// The new body is written after everything already in the file, so its offset is the old
+/// file length (shifted by however much the header grew). Only the header is rewritten: it
+/// gains a `( d o b )` entry for the new section, and the offsets of existing sections are
+/// moved along with the header so they keep pointing at the same bytes.
+///
+/// If the header records that the file was written with [`VsfBuilder::with_crc`], the new
+/// body is followed by its CRC as well, so the result still passes [`VsfHeader::verify_crc`].
+///
+/// # Example
+///
+/// ```
+/// use vsf::file_format::{append_section, parse_header, read_section, VsfBuilder};
+/// use vsf::vsf::{parse, VsfType};
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let mut record = b"R\xC3\x85<".to_vec();
+/// record.append(&mut VsfType::z(1).flatten()?);
+/// record.append(&mut VsfType::y(1).flatten()?);
+/// record.append(&mut VsfType::c(0).flatten()?);
+/// record.push(b'>');
+///
+/// let record = append_section(&record, "demographics", &VsfType::x("Doe, Jane".to_owned()).flatten()?)?;
+/// let record = append_section(&record, "lab_results", &VsfType::af6(vec![5.4, 140.0]).flatten()?)?;
+///
+/// match parse(read_section(&record, "demographics")?, &mut 0)? {
+///     VsfType::x(name) => assert_eq!(name, "Doe, Jane"),
+///     other => panic!("Unexpected {:?}", other),
+/// }
+/// match parse(read_section(&record, "lab_results")?, &mut 0)? {
+///     VsfType::af6(values) => assert_eq!(values, vec![5.4, 140.0]),
+///     other => panic!("Unexpected {:?}", other),
+/// }
+///
+/// let checked = VsfBuilder::new()
+///     .with_crc(true)
+///     .unboxed_section("a", vec![1, 2, 3])
+///     .build()?;
+/// let checked = append_section(&checked, "b", &[4, 5])?;
+/// assert!(parse_header(&checked)?.verify_crc(&checked).is_ok());
+/// assert!(parse_header(&record)?.verify_crc(&record).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn append_section(
+    existing: &[u8],
+    label: &str,
+    body: &[u8],
+) -> Result<Vec<u8>, std::io::Error> {
+    validate_name(label)?;
+    let header = parse_header(existing)?;
+    if header
+        .labels
+        .iter()
+        .any(|definition| definition.label == label)
+    {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("Section '{}' already exists!", label),
+        ));
+    }
+
+    // Offsets relative to the end of the header survive a header rewrite unchanged
+    let old_header_bits = header.length;
+    let mut labels = Vec::with_capacity(header.labels.len() + 1);
+    for definition in &header.labels {
+        if definition.offset < old_header_bits {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Section '{}' starts inside the header!", definition.label),
+            ));
+        }
+        labels.push(LabelDefinition {
+            offset: definition.offset - old_header_bits,
+            ..definition.clone()
+        });
+    }
+    labels.push(LabelDefinition {
+        label: label.to_owned(),
+        offset: existing.len() * 8 - header.length,
+        length: body.len() * 8,
+        count: None,
+    });
+
+    let mut flat = write_header(&VsfHeader {
+        labels,
+        ..header.clone()
+    })?;
+    flat.extend_from_slice(&existing[header.length / 8..]);
+    flat.extend_from_slice(body);
+    if header.crc {
+        flat.append(&mut VsfType::u5(crc32fast::hash(body)).flatten()?);
+    }
+    Ok(flat)
+}
+
+pub(crate) fn section_bytes<'a>(
+    data: &'a [u8],
+    definition: &LabelDefinition,
+) -> Result<&'a [u8], std::io::Error> {
+    if !definition.offset.is_multiple_of(8) || !definition.length.is_multiple_of(8) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Section '{}' does not land on a byte boundary!",
+                definition.label
+            ),
+        ));
+    }
+    let start = definition.offset_bytes();
+    let end = start.checked_add(definition.length_bytes());
+    match end {
+        Some(end) if end <= data.len() => Ok(&data[start..end]),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            format!(
+                "Section '{}' runs past the end of the data!",
+                definition.label
+            ),
+        )),
+    }
+}
+
+/// Cheap check that `data` starts at the beginning of a VSF file and holds its whole header.
+///
+/// Only the exact `RÅ<` magic (`R`, `0xC3 0x85`, `<`) and the `b` header length right after
+/// it are read, so a slice that starts mid-file or was cut short before the end of the
+/// header is turned away without parsing anything else. Passing does not mean the rest of
+/// the header is valid; [`parse_header`] still checks that.
+///
+/// # Example
+///
+/// ```
+/// use vsf::file_format::{parse_header, quick_validate, VsfBuilder};
+///
+/// let file = VsfBuilder::new()
+///     .unboxed_section("tile", vec![9; 32])
+///     .build()
+///     .unwrap();
+/// assert!(quick_validate(&file));
+/// assert!(!quick_validate(&file[1..]));
+///
+/// let header_end = parse_header(&file).unwrap().length / 8;
+/// assert!(quick_validate(&file[..header_end]));
+/// assert!(!quick_validate(&file[..header_end - 1]));
+/// ```
+pub fn quick_validate(data: &[u8]) -> bool {
+    if !data.starts_with(MAGIC) {
+        return false;
+    }
+    let mut pointer = MAGIC.len();
+    match read_header_length(data, &mut pointer) {
+        Ok(Some(length)) => length / 8 <= data.len(),
+        _ => false,
+    }
+}
+
+/// Checks the magic number and version, then reads the header and its label definitions.
+///
+/// Files whose backward version is newer than [`VSF_VERSION`], or whose version is older
+/// than [`VSF_BACKWARD_COMPAT`], are rejected with [`std::io::ErrorKind::Unsupported`].
+///
+/// # Example
+///
+/// ```
+/// use vsf::file_format::{append_section, parse_header, VsfBuilder, MAGIC, VSF_VERSION};
+/// use vsf::vsf::{decode_usize_inclusive, VsfType};
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let mut file = b"R\xC3\x85<".to_vec();
+/// file.append(&mut VsfType::z(VSF_VERSION).flatten()?);
+/// file.append(&mut VsfType::y(VSF_VERSION).flatten()?);
+/// file.push(b'>');
+/// let file = append_section(&file, "thumbnail", &[0u8; 16])?;
+///
+/// let header = parse_header(&file)?;
+/// assert_eq!(header.version, VSF_VERSION);
+/// assert_eq!(header.labels[0].label, "thumbnail");
+/// assert_eq!(header.labels[0].length, 16 * 8);
+///
+/// // The length right after the magic counts its own bytes
+/// let built = VsfBuilder::new().unboxed_section("a", vec![0; 4]).build()?;
+/// assert_eq!(built[MAGIC.len()], b'b');
+/// let mut pointer = MAGIC.len() + 1;
+/// assert_eq!(decode_usize_inclusive(&built, &mut pointer)?, parse_header(&built)?.length);
+///
+/// assert!(parse_header(b"\x89PNG\r\n\x1a\n").is_err());
+///
+/// let mut too_new = b"R\xC3\x85<".to_vec();
+/// too_new.append(&mut VsfType::z(VSF_VERSION + 1).flatten()?);
+/// too_new.append(&mut VsfType::y(VSF_VERSION + 1).flatten()?);
+/// too_new.push(b'>');
+/// let error = parse_header(&too_new).unwrap_err();
+/// assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_header(data: &[u8]) -> Result<VsfHeader, std::io::Error> {
+    if !data.starts_with(MAGIC) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Missing VSF magic number!",
+        ));
+    }
+    let mut pointer = MAGIC.len();
+    let header_length = read_header_length(data, &mut pointer)?;
+    let mut version = 0;
+    let mut backward_version = 0;
+    let mut label_count = None;
+    let mut labels = Vec::new();
+    let mut source_name = None;
+    let mut content_type = None;
+    let mut crc = false;
+
+    loop {
+        match data.get(pointer) {
+            Some(b'>') => {
+                pointer += 1;
+                break;
+            }
+            Some(b'(') => {
+                pointer += 1;
+                labels.push(read_label_definition(data, &mut pointer)?);
+            }
+            Some(_) => match parse(data, &mut pointer)? {
+                VsfType::z(value) => version = value,
+                VsfType::y(value) => backward_version = value,
+                VsfType::c(count) => label_count = Some(count),
+                // Labelled metadata, the names this reader does not know are skipped
+                VsfType::l(name) => match (name.as_str(), parse(data, &mut pointer)?) {
+                    ("source_name", VsfType::x(value)) => source_name = Some(value),
+                    ("content_type", VsfType::x(value)) => content_type = Some(value),
+                    ("crc", VsfType::u0(value)) => crc = value,
+                    _ => {}
+                },
+                other => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Unexpected {:?} in header!", other),
+                    ))
+                }
+            },
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "Header is not terminated!",
+                ))
+            }
+        }
+    }
+
+    if header_length.is_some_and(|length| length != pointer * 8) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Header length does not match the header!",
+        ));
+    }
+    if label_count.is_some_and(|count| count != labels.len()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Label count does not match the label definitions!",
+        ));
+    }
+
+    if backward_version > VSF_VERSION || version < VSF_BACKWARD_COMPAT {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!(
+                "Unsupported VSF version {} (needs a version {} reader), this reader handles versions {} to {}!",
+                version, backward_version, VSF_BACKWARD_COMPAT, VSF_VERSION
+            ),
+        ));
+    }
+
+    Ok(VsfHeader {
+        version,
+        backward_version,
+        length: pointer * 8,
+        labels,
+        source_name,
+        content_type,
+        crc,
+    })
+}
+
+/// Reads the `b` header length that directly follows the magic bytes, if there is one.
+///
+/// The length is written inclusively (see [`decode_usize_inclusive`]), so the header length
+/// in bits is what comes back.
+fn read_header_length(data: &[u8], pointer: &mut usize) -> Result<Option<usize>, std::io::Error> {
+    if data.get(*pointer) != Some(&b'b') {
+        return Ok(None);
+    }
+    *pointer += 1;
+    decode_usize_inclusive(data, pointer).map(Some)
+}
+
+fn read_label_definition(
+    data: &[u8],
+    pointer: &mut usize,
+) -> Result<LabelDefinition, std::io::Error> {
+    let mut label = None;
+    let mut offset = None;
+    let mut length = None;
+    let mut count = None;
+    loop {
+        match data.get(*pointer) {
+            Some(b')') => {
+                *pointer += 1;
+                break;
+            }
+            Some(_) => match parse(data, pointer)? {
+                VsfType::d(value) | VsfType::l(value) => label = Some(value),
+                VsfType::o(value) => offset = Some(value),
+                VsfType::b(value) => length = Some(value),
+                VsfType::c(value) => count = Some(value),
+                other => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Unexpected {:?} in label definition!", other),
+                    ))
+                }
+            },
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "Label definition is not terminated!",
+                ))
+            }
+        }
+    }
+    match (label, offset, length) {
+        (Some(label), Some(offset), Some(length)) => Ok(LabelDefinition {
+            label,
+            offset,
+            length,
+            count,
+        }),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Label definition needs a name, offset and length!",
+        )),
+    }
+}
+
+/// Settles the header length for `labels` and turns their offsets into absolute ones.
+///
+/// On entry each offset is relative to the end of the header; on return it is relative to
+/// the start of the file, and the returned value is the header length in bytes. The header
+/// length and the absolute offsets both depend on how many bytes their own encodings take,
+/// so the header is re-flattened until its length stops changing (the header length itself is
+/// written inclusively, so its width is chosen for the value plus its own bytes). Starting
+/// from zero, every
+/// pass can only grow the values (and so their encoded widths), which guarantees the loop
+/// settles, and it settles on the smallest header that holds them.
+///
+/// # Example
+///
+/// ```
+/// use vsf::file_format::{stabilize_lengths, LabelDefinition, VSF_BACKWARD_COMPAT, VSF_VERSION};
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let mut labels = vec![
+///     LabelDefinition { label: "thumbnail".to_owned(), offset: 0, length: 64, count: None },
+///     LabelDefinition { label: "image".to_owned(), offset: 64, length: 8000, count: None },
+/// ];
+/// let header_length = stabilize_lengths(VSF_VERSION, VSF_BACKWARD_COMPAT, &mut labels)?;
+///
+/// // The first pass encodes every offset in one byte, the header pushes them past 255 bits
+/// assert!(header_length * 8 > u8::MAX as usize);
+/// assert_eq!(labels[0].offset, header_length * 8);
+/// assert_eq!(labels[1].offset, header_length * 8 + 64);
+/// # Ok(())
+/// # }
+/// ```
+pub fn stabilize_lengths(
+    version: usize,
+    backward_version: usize,
+    labels: &mut [LabelDefinition],
+) -> Result<usize, std::io::Error> {
+    let mut header = VsfHeader {
+        version,
+        backward_version,
+        length: 0,
+        labels: labels.to_vec(),
+        source_name: None,
+        content_type: None,
+        crc: false,
+    };
+    let header_length = stabilize(&mut header)?;
+    labels.clone_from_slice(&header.labels);
+    Ok(header_length)
+}
+
+/// [`stabilize_lengths`] for a whole header, metadata included.
+fn stabilize(header: &mut VsfHeader) -> Result<usize, std::io::Error> {
+    let relative: Vec<usize> = header
+        .labels
+        .iter()
+        .map(|definition| definition.offset)
+        .collect();
+    let mut header_length = 0;
+    loop {
+        for (definition, offset) in header.labels.iter_mut().zip(&relative) {
+            definition.offset = header_length * 8 + offset;
+        }
+        let length = flatten_header(header, header_length)?.len();
+        if length == header_length {
+            return Ok(header_length);
+        }
+        header_length = length;
+    }
+}
+
+/// Writes `header`, whose label offsets are relative to the end of the header.
+fn write_header(header: &VsfHeader) -> Result<Vec<u8>, std::io::Error> {
+    let mut header = header.clone();
+    let header_length = stabilize(&mut header)?;
+    flatten_header(&header, header_length)
+}
+
+/// Flattens a header whose length and absolute offsets are already known.
+fn flatten_header(header: &VsfHeader, header_length: usize) -> Result<Vec<u8>, std::io::Error> {
+    let mut flat = MAGIC.to_vec();
+    flat.push(b'b');
+    flat.extend_from_slice(&(header_length * 8).encode_number(true));
+    flat.append(&mut VsfType::z(header.version).flatten()?);
+    flat.append(&mut VsfType::y(header.backward_version).flatten()?);
+    flat.append(&mut VsfType::c(header.labels.len()).flatten()?);
+    if let Some(name) = &header.source_name {
+        flat.append(&mut VsfType::l("source_name".to_owned()).flatten()?);
+        flat.append(&mut VsfType::x(name.clone()).flatten()?);
+    }
+    if let Some(content_type) = &header.content_type {
+        flat.append(&mut VsfType::l("content_type".to_owned()).flatten()?);
+        flat.append(&mut VsfType::x(content_type.clone()).flatten()?);
+    }
+    if header.crc {
+        flat.append(&mut VsfType::l("crc".to_owned()).flatten()?);
+        flat.append(&mut VsfType::u0(true).flatten()?);
+    }
+    for definition in &header.labels {
+        flat.push(b'(');
+        flat.append(&mut VsfType::d(definition.label.clone()).flatten()?);
+        flat.append(&mut VsfType::o(definition.offset).flatten()?);
+        flat.append(&mut VsfType::b(definition.length).flatten()?);
+        if let Some(count) = definition.count {
+            flat.append(&mut VsfType::c(count).flatten()?);
+        }
+        flat.push(b')');
+    }
+    flat.push(b'>');
+    Ok(flat)
+}
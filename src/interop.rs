@@ -0,0 +1,160 @@
+//! Conversions between VSF data and other formats, for debugging and for web tools.
+
+use crate::file_format::{parse_header, MAGIC};
+use crate::vsf::{parse_all, VsfType};
+use serde_json::{json, Value};
+
+/// Renders a VSF file (or a plain run of values) as JSON, every value tagged with its type.
+///
+/// Files with a `RÅ<` header come out as their version and a list of sections, each section
+/// listing the values in its body. Sections that hold raw blobs rather than VSF values only
+/// report their offset and length. With `inline_arrays` off, arrays are summarized by their
+/// element count and payload size instead of being written out. 128-bit integers are written
+/// as decimal strings, since JSON numbers cannot hold them, and NaN or infinite floats become
+/// `null`.
+///
+/// # Example
+///
+/// ```
+/// use vsf::file_format::VsfBuilder;
+/// use vsf::interop::to_json;
+/// use vsf::vsf::VsfType;
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let file = VsfBuilder::new()
+///     .unboxed_section("samples", VsfType::au4(vec![7; 1024]).flatten()?)
+///     .build()?;
+///
+/// let json = to_json(&file, false)?;
+/// assert!(json.contains(r#"{"bytes":2048,"length":1024,"type":"au4"}"#));
+///
+/// let json = to_json(&VsfType::u5(42).flatten()?, true)?;
+/// assert_eq!(json, r#"{"values":[{"type":"u5","value":42}]}"#);
+/// # Ok(())
+/// # }
+/// ```
+pub fn to_json(data: &[u8], inline_arrays: bool) -> Result<String, std::io::Error> {
+    let document = if data.starts_with(MAGIC) {
+        let header = parse_header(data)?;
+        let mut sections = Vec::with_capacity(header.labels.len());
+        for definition in &header.labels {
+            let start = definition.offset_bytes();
+            let end = start
+                .saturating_add(definition.length_bytes())
+                .min(data.len());
+            let mut section = json!({
+                "label": definition.label,
+                "offset": start,
+                "length": definition.length_bytes(),
+            });
+            if let Ok(values) = parse_all(data.get(start..end).unwrap_or_default()) {
+                section["values"] = values_json(&values, inline_arrays);
+            }
+            sections.push(section);
+        }
+        json!({
+            "version": header.version,
+            "backward_version": header.backward_version,
+            "sections": sections,
+        })
+    } else {
+        json!({ "values": values_json(&parse_all(data)?, inline_arrays) })
+    };
+    serde_json::to_string(&document).map_err(std::io::Error::other)
+}
+
+fn values_json(values: &[VsfType], inline_arrays: bool) -> Value {
+    Value::Array(
+        values
+            .iter()
+            .map(|value| value_json(value, inline_arrays))
+            .collect(),
+    )
+}
+
+fn value_json(value: &VsfType, inline_arrays: bool) -> Value {
+    match value {
+        VsfType::u(value) => tagged("u", json!(value)),
+        VsfType::u1(value) => tagged("u1", json!(value)),
+        VsfType::u2(value) => tagged("u2", json!(value)),
+        VsfType::u3(value) => tagged("u3", json!(value)),
+        VsfType::u4(value) => tagged("u4", json!(value)),
+        VsfType::u5(value) => tagged("u5", json!(value)),
+        VsfType::u6(value) => tagged("u6", json!(value)),
+        VsfType::u7(value) => tagged("u7", json!(value.to_string())),
+        VsfType::s(value) => tagged("s", json!(value)),
+        VsfType::s3(value) => tagged("s3", json!(value)),
+        VsfType::s4(value) => tagged("s4", json!(value)),
+        VsfType::s5(value) => tagged("s5", json!(value)),
+        VsfType::s6(value) => tagged("s6", json!(value)),
+        VsfType::s7(value) => tagged("s7", json!(value.to_string())),
+        VsfType::f5(value) => tagged("f5", json!(value)),
+        VsfType::f6(value) => tagged("f6", json!(value)),
+        VsfType::i6(value) => tagged("i6", json!({ "re": value.re, "im": value.im })),
+        VsfType::i7(value) => tagged("i7", json!({ "re": value.re, "im": value.im })),
+        VsfType::u0(value) => tagged("u0", json!(value)),
+        VsfType::au0(values) => array("au0", values, values.len().div_ceil(8), inline_arrays),
+        VsfType::au3(values) => array("au3", values, size_of_val(&values[..]), inline_arrays),
+        VsfType::au4(values) => array("au4", values, size_of_val(&values[..]), inline_arrays),
+        VsfType::au5(values) => array("au5", values, size_of_val(&values[..]), inline_arrays),
+        VsfType::au6(values) => array("au6", values, size_of_val(&values[..]), inline_arrays),
+        VsfType::au7(values) => {
+            let strings: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+            array("au7", &strings, size_of_val(&values[..]), inline_arrays)
+        }
+        VsfType::as3(values) => array("as3", values, size_of_val(&values[..]), inline_arrays),
+        VsfType::as4(values) => array("as4", values, size_of_val(&values[..]), inline_arrays),
+        VsfType::as5(values) => array("as5", values, size_of_val(&values[..]), inline_arrays),
+        VsfType::as6(values) => array("as6", values, size_of_val(&values[..]), inline_arrays),
+        VsfType::as7(values) => {
+            let strings: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+            array("as7", &strings, size_of_val(&values[..]), inline_arrays)
+        }
+        VsfType::af5(values) => array("af5", values, size_of_val(&values[..]), inline_arrays),
+        VsfType::af6(values) => array("af6", values, size_of_val(&values[..]), inline_arrays),
+        VsfType::ai6(values) => {
+            let pairs: Vec<Value> = values
+                .iter()
+                .map(|value| json!({ "re": value.re, "im": value.im }))
+                .collect();
+            array("ai6", &pairs, size_of_val(&values[..]), inline_arrays)
+        }
+        VsfType::ai7(values) => {
+            let pairs: Vec<Value> = values
+                .iter()
+                .map(|value| json!({ "re": value.re, "im": value.im }))
+                .collect();
+            array("ai7", &pairs, size_of_val(&values[..]), inline_arrays)
+        }
+        VsfType::x(value) => tagged("x", json!(value)),
+        VsfType::d(value) => tagged("d", json!(value)),
+        VsfType::l(value) => tagged("l", json!(value)),
+        VsfType::o(value) => tagged("o", json!(value)),
+        VsfType::b(value) => tagged("b", json!(value)),
+        VsfType::c(value) => tagged("c", json!(value)),
+        VsfType::z(value) => tagged("z", json!(value)),
+        VsfType::y(value) => tagged("y", json!(value)),
+        VsfType::m(value) => tagged("m", json!(value)),
+        VsfType::r(value) => tagged("r", json!(value)),
+        VsfType::k(value) => tagged("k", json!(value)),
+        VsfType::e(value) => tagged("e", json!(value)),
+        VsfType::h(value) => tagged("h", json!(hex(value))),
+        VsfType::g(value) => tagged("g", json!(hex(value))),
+    }
+}
+
+fn tagged(tag: &str, value: Value) -> Value {
+    json!({ "type": tag, "value": value })
+}
+
+fn array<T: serde::Serialize>(tag: &str, values: &[T], bytes: usize, inline: bool) -> Value {
+    if inline {
+        json!({ "type": tag, "values": values })
+    } else {
+        json!({ "type": tag, "length": values.len(), "bytes": bytes })
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -34,7 +34,7 @@
 /// # Example
 ///
 /// ```
-/// use vsf::{VsfType, parse, EncodeNumber};
+/// use vsf::vsf::{VsfType, parse, EncodeNumber};
 ///
 /// fn main() -> () {
 ///     let mut vsf_vector = Vec::new();
@@ -69,11 +69,30 @@
 pub mod vsf {
     use num_complex::Complex;
 
-    #[derive(Debug)]
+    /// A single VSF value.
+    ///
+    /// With the `serde` feature enabled, values serialize as `{"variant": value}`:
+    ///
+    /// ```
+    /// # #[cfg(feature = "serde")]
+    /// # {
+    /// use vsf::vsf::VsfType;
+    ///
+    /// for value in [VsfType::u5(42), VsfType::f6(-0.5), VsfType::au4(vec![1, 2, 65535])] {
+    ///     let json = serde_json::to_string(&value).unwrap();
+    ///     assert_eq!(serde_json::from_str::<VsfType>(&json).unwrap(), value);
+    /// }
+    /// assert_eq!(serde_json::to_string(&VsfType::u5(42)).unwrap(), r#"{"u5":42}"#);
+    /// # }
+    /// ```
+    #[derive(Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     #[allow(non_camel_case_types)]
     pub enum VsfType {
         // Unsigned Integer Types
         u(usize), // Unsigned integer, size is determined by the value
+        u1(u8),   // Unsigned 2-bit integer, stored in the top bits of one byte
+        u2(u8),   // Unsigned 4-bit integer, stored in the top bits of one byte
         u3(u8),   // Unsigned 8-bit integer, 2^n notation (2^3=8 bits)
         u4(u16),  // Unsigned 16-bit integer, 2^n notation (2^4=16 bits)
         u5(u32),  // Unsigned 32-bit integer, 2^n notation (2^5=32 bits)
@@ -117,9 +136,9 @@ pub mod vsf {
         ai7(Vec<Complex<f64>>), // Array of complex numbers with f64 components
 
         // Special Types
-        u0(bool),       // Boolean, stored 8 bit aligned, recomend filling all 8 bits
+        u0(bool),       // Boolean, stored as u followed by 0x00 or 0xFF
         au0(Vec<bool>), // Array of Boolean, extra bits are filled with 0 to align to 8 bits
-        x(String),      // Unicode text
+        x(String),      // Unicode text, stored as its UTF-8 bytes
 
         // VSF-specific Types
         d(String),  // Data type
@@ -138,6 +157,54 @@ pub mod vsf {
     }
 
     impl VsfType {
+        /// Encodes the value as its marker, size digit where the type has one, and big-endian
+        /// bytes.
+        ///
+        /// Every fixed-width integer writes all three parts:
+        ///
+        /// ```
+        /// use vsf::vsf::VsfType;
+        ///
+        /// assert_eq!(VsfType::s3(-42).flatten().unwrap(), [b's', b'3', 0xD6]);
+        /// assert_eq!(VsfType::u3(42).flatten().unwrap(), [b'u', b'3', 42]);
+        /// assert_eq!(VsfType::s4(-2).flatten().unwrap(), [b's', b'4', 0xFF, 0xFE]);
+        /// ```
+        ///
+        /// The auto-sized `s` picks the smallest two's complement width that holds the value and
+        /// parses back as that fixed-width variant:
+        ///
+        /// ```
+        /// use vsf::vsf::{parse, VsfType};
+        ///
+        /// for (value, flat, parsed) in [
+        ///     (127, vec![b's', b'3', 0x7F], VsfType::s3(127)),
+        ///     (128, vec![b's', b'4', 0x00, 0x80], VsfType::s4(128)),
+        ///     (-128, vec![b's', b'3', 0x80], VsfType::s3(-128)),
+        ///     (-129, vec![b's', b'4', 0xFF, 0x7F], VsfType::s4(-129)),
+        ///     (32767, vec![b's', b'4', 0x7F, 0xFF], VsfType::s4(32767)),
+        ///     (-32768, vec![b's', b'4', 0x80, 0x00], VsfType::s4(-32768)),
+        ///     (-32769, vec![b's', b'5', 0xFF, 0xFF, 0x7F, 0xFF], VsfType::s5(-32769)),
+        /// ] {
+        ///     assert_eq!(VsfType::s(value).flatten().unwrap(), flat);
+        ///     assert_eq!(parse(&flat, &mut 0).unwrap(), parsed);
+        /// }
+        /// ```
+        ///
+        /// `u1` and `u2` hold 2-bit and 4-bit values in the top bits of their byte:
+        ///
+        /// ```
+        /// use vsf::vsf::{parse, VsfType};
+        ///
+        /// let flat = VsfType::u1(3).flatten().unwrap();
+        /// assert_eq!(flat, [b'u', b'1', 0b1100_0000]);
+        /// assert_eq!(parse(&flat, &mut 0).unwrap(), VsfType::u1(3));
+        ///
+        /// let flat = VsfType::u2(15).flatten().unwrap();
+        /// assert_eq!(flat, [b'u', b'2', 0b1111_0000]);
+        /// assert_eq!(parse(&flat, &mut 0).unwrap(), VsfType::u2(15));
+        ///
+        /// assert!(VsfType::u1(4).flatten().is_err());
+        /// ```
         pub fn flatten(&self) -> Result<Vec<u8>, std::io::Error> {
             match self {
                 // Unsigned Integer Types
@@ -155,6 +222,12 @@ pub mod vsf {
                     flat.extend_from_slice(&value.encode_number(false));
                     Ok(flat)
                 }
+                VsfType::u1(value) if *value < 4 => Ok(vec![b'u', b'1', *value << 6]),
+                VsfType::u2(value) if *value < 16 => Ok(vec![b'u', b'2', *value << 4]),
+                VsfType::u1(_) | VsfType::u2(_) => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "Value does not fit in its sub-byte width!",
+                )),
                 VsfType::u3(value) => Ok(vec![b'u', b'3', *value]),
                 VsfType::u4(value) => {
                     let bytes = value.to_be_bytes();
@@ -183,7 +256,7 @@ pub mod vsf {
                 // Signed Integer Types
                 VsfType::s(value) => {
                     let mut flat = vec![b's'];
-                    flat.extend_from_slice(&(*value as usize).encode_number(false));
+                    flat.extend_from_slice(&value.encode_number(false));
                     Ok(flat)
                 }
                 VsfType::s3(value) => Ok(vec![b's', b'3', *value as u8]),
@@ -224,6 +297,21 @@ pub mod vsf {
                     ])
                 }
 
+                // Boolean Vector, packed 8 per byte from the most significant bit, padding bits zeroed
+                VsfType::au0(values) => {
+                    let mut flat = Vec::new();
+                    flat.push(b'a');
+                    flat.extend_from_slice(&values.len().encode_number(false));
+                    flat.push(b'u');
+                    flat.push(b'0');
+                    let mut bits = crate::bitio::BitWriter::new();
+                    for value in values {
+                        bits.write_bits(*value as u64, 1);
+                    }
+                    flat.append(&mut bits.into_bytes());
+                    Ok(flat)
+                }
+
                 // Unsigned Integer Vectors
                 VsfType::au3(values) => {
                     let mut flat = Vec::new();
@@ -440,7 +528,40 @@ pub mod vsf {
                     Ok(flat)
                 }
 
+                // Hash
+                VsfType::h(value) => {
+                    let mut flat = Vec::new();
+                    flat.push(b'h');
+                    flat.extend_from_slice(&(value.len() * 8).encode_number(false));
+                    flat.extend_from_slice(value);
+                    Ok(flat)
+                }
+
                 // VSF specific types
+                VsfType::m(value) => {
+                    let mut flat = Vec::new();
+                    flat.push(b'm');
+                    flat.extend_from_slice(&value.encode_number(false));
+                    Ok(flat)
+                }
+                VsfType::r(value) => {
+                    let mut flat = Vec::new();
+                    flat.push(b'r');
+                    flat.extend_from_slice(&value.encode_number(false));
+                    Ok(flat)
+                }
+                VsfType::k(value) => {
+                    let mut flat = Vec::new();
+                    flat.push(b'k');
+                    flat.extend_from_slice(&value.encode_number(false));
+                    Ok(flat)
+                }
+                VsfType::e(value) => {
+                    let mut flat = Vec::new();
+                    flat.push(b'e');
+                    flat.extend_from_slice(&value.encode_number(false));
+                    Ok(flat)
+                }
                 VsfType::z(value) => {
                     let mut flat = Vec::new();
                     flat.push(b'z');
@@ -485,15 +606,766 @@ pub mod vsf {
                     flat.extend_from_slice(&value.encode_number(false));
                     Ok(flat)
                 }
-                _ => Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    "Unsupported type for flattening!",
-                )),
             }
         }
+
+        /// Flattens the value into its one canonical byte form, for hashing and signing.
+        ///
+        /// Whenever two values are logically equal, their canonical bytes are identical:
+        ///
+        /// - Integers (`u`, `u3`..`u7`, `s`, `s3`..`s7`) use the smallest of the 8, 16, 32, 64
+        ///   and 128 bit widths that holds the value, so `u6(5)` and `u3(5)` agree.
+        /// - Lengths, counts, offsets, versions, markers and frame numbers use the smallest
+        ///   width that holds them.
+        /// - Any NaN, in a scalar, array or complex number, becomes the quiet NaN
+        ///   (`0x7FC00000` / `0x7FF8000000000000`). Negative zero is kept, it is a distinct value.
+        /// - Booleans use the short `u` + `0x00`/`0xFF` form.
+        ///
+        /// Everything else flattens exactly as [`VsfType::flatten`] does.
+        ///
+        /// ```
+        /// use vsf::vsf::VsfType;
+        ///
+        /// let quiet = VsfType::f6(f64::NAN);
+        /// let signalling = VsfType::f6(f64::from_bits(0x7FF0_0000_0000_0001));
+        /// assert_ne!(quiet.flatten().unwrap(), signalling.flatten().unwrap());
+        /// assert_eq!(
+        ///     quiet.flatten_canonical().unwrap(),
+        ///     signalling.flatten_canonical().unwrap()
+        /// );
+        ///
+        /// assert_eq!(
+        ///     VsfType::u6(5).flatten_canonical().unwrap(),
+        ///     VsfType::u3(5).flatten_canonical().unwrap()
+        /// );
+        ///
+        /// for value in [VsfType::m(200), VsfType::r(200), VsfType::k(200), VsfType::e(200)] {
+        ///     assert_eq!(value.flatten().unwrap().len(), 4);
+        ///     assert_eq!(value.flatten_canonical().unwrap(), [value.marker(), b'3', 200]);
+        /// }
+        /// ```
+        pub fn flatten_canonical(&self) -> Result<Vec<u8>, std::io::Error> {
+            match self {
+                VsfType::u(value) => Ok(canonical_unsigned(*value as u128)),
+                VsfType::u1(value) | VsfType::u2(value) | VsfType::u3(value) => {
+                    Ok(canonical_unsigned(*value as u128))
+                }
+                VsfType::u4(value) => Ok(canonical_unsigned(*value as u128)),
+                VsfType::u5(value) => Ok(canonical_unsigned(*value as u128)),
+                VsfType::u6(value) => Ok(canonical_unsigned(*value as u128)),
+                VsfType::u7(value) => Ok(canonical_unsigned(*value)),
+                VsfType::s(value) => Ok(canonical_signed(*value as i128)),
+                VsfType::s3(value) => Ok(canonical_signed(*value as i128)),
+                VsfType::s4(value) => Ok(canonical_signed(*value as i128)),
+                VsfType::s5(value) => Ok(canonical_signed(*value as i128)),
+                VsfType::s6(value) => Ok(canonical_signed(*value as i128)),
+                VsfType::s7(value) => Ok(canonical_signed(*value)),
+                VsfType::f5(value) => VsfType::f5(canonical_f32(*value)).flatten(),
+                VsfType::f6(value) => VsfType::f6(canonical_f64(*value)).flatten(),
+                VsfType::i6(value) => VsfType::i6(Complex {
+                    re: canonical_f32(value.re),
+                    im: canonical_f32(value.im),
+                })
+                .flatten(),
+                VsfType::i7(value) => VsfType::i7(Complex {
+                    re: canonical_f64(value.re),
+                    im: canonical_f64(value.im),
+                })
+                .flatten(),
+                VsfType::af5(values) => {
+                    let values = values.iter().map(|value| canonical_f32(*value)).collect();
+                    canonical_length(VsfType::af5(values).flatten()?, values_len(self))
+                }
+                VsfType::af6(values) => {
+                    let values = values.iter().map(|value| canonical_f64(*value)).collect();
+                    canonical_length(VsfType::af6(values).flatten()?, values_len(self))
+                }
+                VsfType::ai6(values) => {
+                    let values = values
+                        .iter()
+                        .map(|value| Complex {
+                            re: canonical_f32(value.re),
+                            im: canonical_f32(value.im),
+                        })
+                        .collect();
+                    canonical_length(VsfType::ai6(values).flatten()?, values_len(self))
+                }
+                VsfType::ai7(values) => {
+                    let values = values
+                        .iter()
+                        .map(|value| Complex {
+                            re: canonical_f64(value.re),
+                            im: canonical_f64(value.im),
+                        })
+                        .collect();
+                    canonical_length(VsfType::ai7(values).flatten()?, values_len(self))
+                }
+                VsfType::au0(_)
+                | VsfType::au3(_)
+                | VsfType::au4(_)
+                | VsfType::au5(_)
+                | VsfType::au6(_)
+                | VsfType::au7(_)
+                | VsfType::as3(_)
+                | VsfType::as4(_)
+                | VsfType::as5(_)
+                | VsfType::as6(_)
+                | VsfType::as7(_)
+                | VsfType::x(_)
+                | VsfType::d(_)
+                | VsfType::l(_)
+                | VsfType::o(_)
+                | VsfType::b(_)
+                | VsfType::c(_)
+                | VsfType::z(_)
+                | VsfType::y(_)
+                | VsfType::h(_)
+                | VsfType::g(_)
+                | VsfType::m(_)
+                | VsfType::r(_)
+                | VsfType::k(_)
+                | VsfType::e(_) => canonical_length(self.flatten()?, values_len(self)),
+                VsfType::u0(_) => self.flatten(),
+            }
+        }
+    }
+
+    impl VsfType {
+        /// Builds a boolean array, stored packed eight to a byte starting from the most
+        /// significant bit. Bits past the last value in the final byte are always written as
+        /// zero, so nothing left over in memory ends up in the file.
+        ///
+        /// ```
+        /// use vsf::vsf::{parse, VsfType};
+        ///
+        /// let flags = VsfType::from_bools(&[true, false, true, true, true]);
+        /// let flat = flags.flatten().unwrap();
+        /// assert_eq!(flat, [b'a', b'3', 5, b'u', b'0', 0b1011_1000]);
+        /// assert_eq!(parse(&flat, &mut 0).unwrap(), flags);
+        /// ```
+        pub fn from_bools(values: &[bool]) -> VsfType {
+            VsfType::au0(values.to_vec())
+        }
+
+        /// Returns the leading type byte this value is flattened with.
+        pub fn marker(&self) -> u8 {
+            match self {
+                VsfType::u(_)
+                | VsfType::u0(_)
+                | VsfType::u1(_)
+                | VsfType::u2(_)
+                | VsfType::u3(_)
+                | VsfType::u4(_)
+                | VsfType::u5(_)
+                | VsfType::u6(_)
+                | VsfType::u7(_) => b'u',
+                VsfType::s(_)
+                | VsfType::s3(_)
+                | VsfType::s4(_)
+                | VsfType::s5(_)
+                | VsfType::s6(_)
+                | VsfType::s7(_) => b's',
+                VsfType::f5(_) | VsfType::f6(_) => b'f',
+                VsfType::au0(_)
+                | VsfType::au3(_)
+                | VsfType::au4(_)
+                | VsfType::au5(_)
+                | VsfType::au6(_)
+                | VsfType::au7(_)
+                | VsfType::as3(_)
+                | VsfType::as4(_)
+                | VsfType::as5(_)
+                | VsfType::as6(_)
+                | VsfType::as7(_)
+                | VsfType::af5(_)
+                | VsfType::af6(_)
+                | VsfType::ai6(_)
+                | VsfType::ai7(_) => b'a',
+                VsfType::i6(_) | VsfType::i7(_) => b'i',
+                VsfType::x(_) => b'x',
+                VsfType::d(_) => b'd',
+                VsfType::l(_) => b'l',
+                VsfType::o(_) => b'o',
+                VsfType::b(_) => b'b',
+                VsfType::c(_) => b'c',
+                VsfType::z(_) => b'z',
+                VsfType::y(_) => b'y',
+                VsfType::m(_) => b'm',
+                VsfType::r(_) => b'r',
+                VsfType::k(_) => b'k',
+                VsfType::e(_) => b'e',
+                VsfType::h(_) => b'h',
+                VsfType::g(_) => b'g',
+            }
+        }
+
+        /// Returns the inner value if this is a `u0`, otherwise `None`. The other `as_*`
+        /// accessors work the same way for their own variant.
+        ///
+        /// ```
+        /// use vsf::vsf::{parse, VsfType};
+        ///
+        /// let flat = VsfType::u5(42).flatten().unwrap();
+        /// let parsed = parse(&flat, &mut 0).unwrap();
+        /// assert_eq!(parsed.as_u32(), Some(42));
+        /// assert_eq!(parsed.as_f64(), None);
+        /// assert_eq!(parsed.marker(), b'u');
+        /// ```
+        pub fn as_bool(&self) -> Option<bool> {
+            match self {
+                VsfType::u0(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_usize(&self) -> Option<usize> {
+            match self {
+                VsfType::u(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_u8(&self) -> Option<u8> {
+            match self {
+                VsfType::u1(value) | VsfType::u2(value) | VsfType::u3(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_u16(&self) -> Option<u16> {
+            match self {
+                VsfType::u4(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_u32(&self) -> Option<u32> {
+            match self {
+                VsfType::u5(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_u64(&self) -> Option<u64> {
+            match self {
+                VsfType::u6(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_u128(&self) -> Option<u128> {
+            match self {
+                VsfType::u7(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_isize(&self) -> Option<isize> {
+            match self {
+                VsfType::s(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_i8(&self) -> Option<i8> {
+            match self {
+                VsfType::s3(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_i16(&self) -> Option<i16> {
+            match self {
+                VsfType::s4(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_i32(&self) -> Option<i32> {
+            match self {
+                VsfType::s5(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_i64(&self) -> Option<i64> {
+            match self {
+                VsfType::s6(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_i128(&self) -> Option<i128> {
+            match self {
+                VsfType::s7(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_f32(&self) -> Option<f32> {
+            match self {
+                VsfType::f5(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_f64(&self) -> Option<f64> {
+            match self {
+                VsfType::f6(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_complex_f32(&self) -> Option<Complex<f32>> {
+            match self {
+                VsfType::i6(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_complex_f64(&self) -> Option<Complex<f64>> {
+            match self {
+                VsfType::i7(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        /// Returns the text of an `x`, `d` or `l` value.
+        pub fn as_string(&self) -> Option<&str> {
+            match self {
+                VsfType::x(value) | VsfType::d(value) | VsfType::l(value) => Some(value),
+                _ => None,
+            }
+        }
+
+        pub fn as_bool_array(&self) -> Option<&[bool]> {
+            match self {
+                VsfType::au0(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_u8_array(&self) -> Option<&[u8]> {
+            match self {
+                VsfType::au3(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_u16_array(&self) -> Option<&[u16]> {
+            match self {
+                VsfType::au4(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_u32_array(&self) -> Option<&[u32]> {
+            match self {
+                VsfType::au5(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_u64_array(&self) -> Option<&[u64]> {
+            match self {
+                VsfType::au6(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_u128_array(&self) -> Option<&[u128]> {
+            match self {
+                VsfType::au7(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_i8_array(&self) -> Option<&[i8]> {
+            match self {
+                VsfType::as3(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_i16_array(&self) -> Option<&[i16]> {
+            match self {
+                VsfType::as4(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_i32_array(&self) -> Option<&[i32]> {
+            match self {
+                VsfType::as5(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_i64_array(&self) -> Option<&[i64]> {
+            match self {
+                VsfType::as6(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_i128_array(&self) -> Option<&[i128]> {
+            match self {
+                VsfType::as7(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_f32_array(&self) -> Option<&[f32]> {
+            match self {
+                VsfType::af5(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_f64_array(&self) -> Option<&[f64]> {
+            match self {
+                VsfType::af6(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_complex_f32_array(&self) -> Option<&[Complex<f32>]> {
+            match self {
+                VsfType::ai6(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_complex_f64_array(&self) -> Option<&[Complex<f64>]> {
+            match self {
+                VsfType::ai7(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_hash(&self) -> Option<&[u8]> {
+            match self {
+                VsfType::h(value) => Some(value),
+                _ => None,
+            }
+        }
+
+        pub fn as_signature(&self) -> Option<&[u8]> {
+            match self {
+                VsfType::g(value) => Some(value),
+                _ => None,
+            }
+        }
+    }
+
+    /// Smallest `u3`..`u7` encoding of an unsigned value.
+    fn canonical_unsigned(value: u128) -> Vec<u8> {
+        if let Ok(value) = u8::try_from(value) {
+            vec![b'u', b'3', value]
+        } else if let Ok(value) = u16::try_from(value) {
+            let mut flat = vec![b'u', b'4'];
+            flat.extend_from_slice(&value.to_be_bytes());
+            flat
+        } else if let Ok(value) = u32::try_from(value) {
+            let mut flat = vec![b'u', b'5'];
+            flat.extend_from_slice(&value.to_be_bytes());
+            flat
+        } else if let Ok(value) = u64::try_from(value) {
+            let mut flat = vec![b'u', b'6'];
+            flat.extend_from_slice(&value.to_be_bytes());
+            flat
+        } else {
+            let mut flat = vec![b'u', b'7'];
+            flat.extend_from_slice(&value.to_be_bytes());
+            flat
+        }
+    }
+
+    /// Smallest `s3`..`s7` encoding of a signed value.
+    fn canonical_signed(value: i128) -> Vec<u8> {
+        if let Ok(value) = i8::try_from(value) {
+            vec![b's', b'3', value as u8]
+        } else if let Ok(value) = i16::try_from(value) {
+            let mut flat = vec![b's', b'4'];
+            flat.extend_from_slice(&value.to_be_bytes());
+            flat
+        } else if let Ok(value) = i32::try_from(value) {
+            let mut flat = vec![b's', b'5'];
+            flat.extend_from_slice(&value.to_be_bytes());
+            flat
+        } else if let Ok(value) = i64::try_from(value) {
+            let mut flat = vec![b's', b'6'];
+            flat.extend_from_slice(&value.to_be_bytes());
+            flat
+        } else {
+            let mut flat = vec![b's', b'7'];
+            flat.extend_from_slice(&value.to_be_bytes());
+            flat
+        }
+    }
+
+    /// Smallest size digit and bytes holding `value`, readable by `decode_usize`.
+    fn canonical_usize(value: usize) -> Vec<u8> {
+        let mut flat = canonical_unsigned(value as u128);
+        flat.remove(0);
+        flat
+    }
+
+    fn canonical_f32(value: f32) -> f32 {
+        if value.is_nan() {
+            f32::NAN
+        } else {
+            value
+        }
+    }
+
+    fn canonical_f64(value: f64) -> f64 {
+        if value.is_nan() {
+            f64::NAN
+        } else {
+            value
+        }
+    }
+
+    /// The number that follows the marker of a length-prefixed or usize-valued type.
+    fn values_len(value: &VsfType) -> usize {
+        match value {
+            VsfType::au0(values) => values.len(),
+            VsfType::au3(values) => values.len(),
+            VsfType::au4(values) => values.len(),
+            VsfType::au5(values) => values.len(),
+            VsfType::au6(values) => values.len(),
+            VsfType::au7(values) => values.len(),
+            VsfType::as3(values) => values.len(),
+            VsfType::as4(values) => values.len(),
+            VsfType::as5(values) => values.len(),
+            VsfType::as6(values) => values.len(),
+            VsfType::as7(values) => values.len(),
+            VsfType::af5(values) => values.len(),
+            VsfType::af6(values) => values.len(),
+            VsfType::ai6(values) => values.len(),
+            VsfType::ai7(values) => values.len(),
+            VsfType::x(value) | VsfType::d(value) | VsfType::l(value) => value.len(),
+            VsfType::h(value) | VsfType::g(value) => value.len() * 8,
+            VsfType::o(value)
+            | VsfType::b(value)
+            | VsfType::c(value)
+            | VsfType::z(value)
+            | VsfType::y(value)
+            | VsfType::m(value)
+            | VsfType::r(value)
+            | VsfType::k(value)
+            | VsfType::e(value) => *value,
+            _ => 0,
+        }
+    }
+
+    /// Swaps the `encode_number` length written after the marker of `flat` for its
+    /// smallest encoding.
+    fn canonical_length(flat: Vec<u8>, length: usize) -> Result<Vec<u8>, std::io::Error> {
+        let written = length.encode_number(false);
+        let mut canonical = vec![flat[0]];
+        canonical.extend_from_slice(&canonical_usize(length));
+        canonical.extend_from_slice(&flat[1 + written.len()..]);
+        Ok(canonical)
+    }
+
+    /// Number of array elements shown before a summary is truncated.
+    const DISPLAY_ELEMENTS: usize = 8;
+
+    fn write_elements<T: std::fmt::Display>(
+        f: &mut std::fmt::Formatter,
+        name: &str,
+        values: &[T],
+    ) -> std::fmt::Result {
+        write!(f, "{} [", name)?;
+        for (index, value) in values.iter().take(DISPLAY_ELEMENTS).enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", value)?;
+        }
+        if values.len() > DISPLAY_ELEMENTS {
+            write!(f, ", …")?;
+        }
+        write!(f, "] ({} elements)", values.len())
+    }
+
+    fn write_bytes(f: &mut std::fmt::Formatter, name: char, bytes: &[u8]) -> std::fmt::Result {
+        write!(f, "{} {} bits ", name, bytes.len() * 8)?;
+        for byte in bytes.iter().take(DISPLAY_ELEMENTS) {
+            write!(f, "{:02x}", byte)?;
+        }
+        if bytes.len() > DISPLAY_ELEMENTS {
+            write!(f, "…")?;
+        }
+        Ok(())
+    }
+
+    /// Short, human readable summary of a value. Arrays and byte strings are truncated to their
+    /// first few elements so that dumping a large file stays readable.
+    impl std::fmt::Display for VsfType {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                VsfType::u(value) => write!(f, "u {}", value),
+                VsfType::u1(value) => write!(f, "u1 {}", value),
+                VsfType::u2(value) => write!(f, "u2 {}", value),
+                VsfType::u3(value) => write!(f, "u3 {}", value),
+                VsfType::u4(value) => write!(f, "u4 {}", value),
+                VsfType::u5(value) => write!(f, "u5 {}", value),
+                VsfType::u6(value) => write!(f, "u6 {}", value),
+                VsfType::u7(value) => write!(f, "u7 {}", value),
+                VsfType::s(value) => write!(f, "s {}", value),
+                VsfType::s3(value) => write!(f, "s3 {}", value),
+                VsfType::s4(value) => write!(f, "s4 {}", value),
+                VsfType::s5(value) => write!(f, "s5 {}", value),
+                VsfType::s6(value) => write!(f, "s6 {}", value),
+                VsfType::s7(value) => write!(f, "s7 {}", value),
+                VsfType::f5(value) => write!(f, "f5 {}", value),
+                VsfType::f6(value) => write!(f, "f6 {}", value),
+                VsfType::au3(values) => write_elements(f, "au3", values),
+                VsfType::au4(values) => write_elements(f, "au4", values),
+                VsfType::au5(values) => write_elements(f, "au5", values),
+                VsfType::au6(values) => write_elements(f, "au6", values),
+                VsfType::au7(values) => write_elements(f, "au7", values),
+                VsfType::as3(values) => write_elements(f, "as3", values),
+                VsfType::as4(values) => write_elements(f, "as4", values),
+                VsfType::as5(values) => write_elements(f, "as5", values),
+                VsfType::as6(values) => write_elements(f, "as6", values),
+                VsfType::as7(values) => write_elements(f, "as7", values),
+                VsfType::af5(values) => write_elements(f, "af5", values),
+                VsfType::af6(values) => write_elements(f, "af6", values),
+                VsfType::i6(value) => write!(f, "i6 {}", value),
+                VsfType::i7(value) => write!(f, "i7 {}", value),
+                VsfType::ai6(values) => write_elements(f, "ai6", values),
+                VsfType::ai7(values) => write_elements(f, "ai7", values),
+                VsfType::u0(value) => write!(f, "u0 {}", value),
+                VsfType::au0(values) => write_elements(f, "au0", values),
+                VsfType::x(value) => write!(f, "x {:?}", value),
+                VsfType::d(value) => write!(f, "d {:?}", value),
+                VsfType::l(value) => write!(f, "l {:?}", value),
+                VsfType::o(value) => write!(f, "o {}", value),
+                VsfType::b(value) => write!(f, "b {}", value),
+                VsfType::c(value) => write!(f, "c {}", value),
+                VsfType::z(value) => write!(f, "z {}", value),
+                VsfType::y(value) => write!(f, "y {}", value),
+                VsfType::m(value) => write!(f, "m {}", value),
+                VsfType::r(value) => write!(f, "r {}", value),
+                VsfType::k(value) => write!(f, "k {}", value),
+                VsfType::e(value) => write!(f, "e {}", value),
+                VsfType::h(value) => write_bytes(f, 'h', value),
+                VsfType::g(value) => write_bytes(f, 'g', value),
+            }
+        }
+    }
+
+    // Fixed-width Rust values convert to the VSF type of the same width
+    impl From<bool> for VsfType {
+        fn from(value: bool) -> Self {
+            VsfType::u0(value)
+        }
+    }
+    impl From<u8> for VsfType {
+        fn from(value: u8) -> Self {
+            VsfType::u3(value)
+        }
+    }
+    impl From<u16> for VsfType {
+        fn from(value: u16) -> Self {
+            VsfType::u4(value)
+        }
+    }
+    impl From<u32> for VsfType {
+        fn from(value: u32) -> Self {
+            VsfType::u5(value)
+        }
+    }
+    impl From<u64> for VsfType {
+        fn from(value: u64) -> Self {
+            VsfType::u6(value)
+        }
+    }
+    impl From<u128> for VsfType {
+        fn from(value: u128) -> Self {
+            VsfType::u7(value)
+        }
+    }
+    impl From<i8> for VsfType {
+        fn from(value: i8) -> Self {
+            VsfType::s3(value)
+        }
+    }
+    impl From<i16> for VsfType {
+        fn from(value: i16) -> Self {
+            VsfType::s4(value)
+        }
+    }
+    impl From<i32> for VsfType {
+        fn from(value: i32) -> Self {
+            VsfType::s5(value)
+        }
+    }
+    impl From<i64> for VsfType {
+        fn from(value: i64) -> Self {
+            VsfType::s6(value)
+        }
+    }
+    impl From<i128> for VsfType {
+        fn from(value: i128) -> Self {
+            VsfType::s7(value)
+        }
+    }
+    impl From<f32> for VsfType {
+        fn from(value: f32) -> Self {
+            VsfType::f5(value)
+        }
+    }
+    impl From<f64> for VsfType {
+        fn from(value: f64) -> Self {
+            VsfType::f6(value)
+        }
+    }
+    impl From<Complex<f32>> for VsfType {
+        fn from(value: Complex<f32>) -> Self {
+            VsfType::i6(value)
+        }
+    }
+    impl From<Complex<f64>> for VsfType {
+        fn from(value: Complex<f64>) -> Self {
+            VsfType::i7(value)
+        }
+    }
+    impl From<String> for VsfType {
+        fn from(value: String) -> Self {
+            VsfType::x(value)
+        }
+    }
+    impl From<&str> for VsfType {
+        fn from(value: &str) -> Self {
+            VsfType::x(value.to_owned())
+        }
     }
 
     /// Encodes the length of a vector into a VSF-style byte vector. Automatically sizes usize, other datatypes are maintained in bit sizes.
+    ///
+    /// With `inclusive` set, the encoded value also counts the size digit and value bytes it is
+    /// written with (2, 3, 5, 9 or 17 bytes), so a length can cover itself. The smallest width
+    /// whose maximum still holds the value plus that overhead is chosen:
+    ///
+    /// ```
+    /// use vsf::vsf::EncodeNumber;
+    ///
+    /// assert_eq!(253usize.encode_number(true), [b'3', 255]);
+    /// assert_eq!(254usize.encode_number(true), [b'4', 0x01, 0x01]);
+    /// assert_eq!(65532usize.encode_number(true), [b'4', 0xFF, 0xFF]);
+    /// assert_eq!(65533usize.encode_number(true), [b'5', 0, 1, 0, 2]);
+    /// assert_eq!(4_294_967_290usize.encode_number(true), [b'5', 0xFF, 0xFF, 0xFF, 0xFF]);
+    /// assert_eq!(
+    ///     4_294_967_291usize.encode_number(true),
+    ///     [b'6', 0, 0, 0, 1, 0, 0, 0, 4]
+    /// );
+    /// assert_eq!(usize::MAX.encode_number(true).len(), if usize::BITS == 64 { 17 } else { 9 });
+    /// ```
     pub trait EncodeNumber {
         fn encode_number(&self, inclusive: bool) -> Vec<u8>;
     }
@@ -579,7 +1451,7 @@ pub mod vsf {
     impl EncodeNumber for u128 {
         fn encode_number(&self, inclusive: bool) -> Vec<u8> {
             if inclusive {
-                let bytes = (*self as u128 + 17).to_be_bytes();
+                let bytes = (*self + 17).to_be_bytes();
                 vec![
                     b'7', bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6],
                     bytes[7], bytes[8], bytes[9], bytes[10], bytes[11], bytes[12], bytes[13],
@@ -599,34 +1471,36 @@ pub mod vsf {
         fn encode_number(&self, inclusive: bool) -> Vec<u8> {
             let mut flat = Vec::new();
             if inclusive {
-                if *self < (std::u8::MAX / 2) as usize {
+                // Widen first so adding the overhead can never overflow, whatever the width of usize
+                let value = *self as u128;
+                if value + 2 <= u8::MAX as u128 {
                     flat.push(b'3'); // Indicate that length fits in one byte (2^n notation, 2^3=8 bits)
-                    flat.push((*self + 2) as u8);
-                } else if *self < (std::u16::MAX / 2) as usize {
+                    flat.push((value + 2) as u8);
+                } else if value + 3 <= u16::MAX as u128 {
                     flat.push(b'4'); // Indicate that length fits in two bytes (2^4=16 bits)
-                    flat.extend_from_slice(&(*self as u16 + 3).to_be_bytes());
-                } else if *self < (std::u32::MAX / 2) as usize {
+                    flat.extend_from_slice(&((value + 3) as u16).to_be_bytes());
+                } else if value + 5 <= u32::MAX as u128 {
                     flat.push(b'5'); // Indicate that length fits in four bytes (2^5=32 bits)
-                    flat.extend_from_slice(&(*self as u32 + 5).to_be_bytes());
-                } else if *self < (std::u64::MAX / 2) as usize {
+                    flat.extend_from_slice(&((value + 5) as u32).to_be_bytes());
+                } else if value + 9 <= u64::MAX as u128 {
                     flat.push(b'6'); // Indicate that length fits in eight bytes (2^6=64 bits)
-                    flat.extend_from_slice(&(*self as u64 + 9).to_be_bytes());
+                    flat.extend_from_slice(&((value + 9) as u64).to_be_bytes());
                 } else {
                     flat.push(b'7'); // Indicate that length fits in sixteen bytes (2^7=128 bits)
-                    flat.extend_from_slice(&(*self as u128 + 17).to_be_bytes());
+                    flat.extend_from_slice(&(value + 17).to_be_bytes());
                 }
                 flat
             } else {
-                if *self < (std::u8::MAX / 2) as usize {
+                if *self < (u8::MAX / 2) as usize {
                     flat.push(b'3'); // Indicate that length fits in one byte (2^n notation, 2^3=8 bits)
                     flat.push(*self as u8);
-                } else if *self < (std::u16::MAX / 2) as usize {
+                } else if *self < (u16::MAX / 2) as usize {
                     flat.push(b'4'); // Indicate that length fits in two bytes (2^4=16 bits)
                     flat.extend_from_slice(&(*self as u16).to_be_bytes());
-                } else if *self < (std::u32::MAX / 2) as usize {
+                } else if *self < (u32::MAX / 2) as usize {
                     flat.push(b'5'); // Indicate that length fits in four bytes (2^5=32 bits)
                     flat.extend_from_slice(&(*self as u32).to_be_bytes());
-                } else if *self < (std::u64::MAX / 2) as usize {
+                } else if *self < (u64::MAX / 2) as usize {
                     flat.push(b'6'); // Indicate that length fits in eight bytes (2^6=64 bits)
                     flat.extend_from_slice(&(*self as u64).to_be_bytes());
                 } else {
@@ -637,57 +1511,191 @@ pub mod vsf {
             }
         }
     }
+    impl EncodeNumber for isize {
+        fn encode_number(&self, inclusive: bool) -> Vec<u8> {
+            // Two's complement at the smallest width that holds the value (plus the bytes it is
+            // written with, when inclusive)
+            let value = *self as i128;
+            let mut flat = Vec::new();
+            let adder = |overhead: i128| if inclusive { value + overhead } else { value };
+            if let Ok(value) = i8::try_from(adder(2)) {
+                flat.push(b'3');
+                flat.extend_from_slice(&value.to_be_bytes());
+            } else if let Ok(value) = i16::try_from(adder(3)) {
+                flat.push(b'4');
+                flat.extend_from_slice(&value.to_be_bytes());
+            } else if let Ok(value) = i32::try_from(adder(5)) {
+                flat.push(b'5');
+                flat.extend_from_slice(&value.to_be_bytes());
+            } else if let Ok(value) = i64::try_from(adder(9)) {
+                flat.push(b'6');
+                flat.extend_from_slice(&value.to_be_bytes());
+            } else {
+                flat.push(b'7');
+                flat.extend_from_slice(&adder(17).to_be_bytes());
+            }
+            flat
+        }
+    }
 
+    /// Parses one VSF value starting at `pointer` and advances `pointer` past it.
+    ///
+    /// Text is stored as raw UTF-8, so any Unicode string comes back exactly as written:
+    ///
+    /// ```
+    /// use vsf::vsf::{parse, VsfType};
+    ///
+    /// for text in ["名前", "🦀🚀", "e\u{301}\u{327}", "Zoë"] {
+    ///     let flat = VsfType::x(text.to_owned()).flatten().unwrap();
+    ///     let mut pointer = 0;
+    ///     match parse(&flat, &mut pointer).unwrap() {
+    ///         VsfType::x(parsed) => assert_eq!(parsed, text),
+    ///         other => panic!("Unexpected {:?}", other),
+    ///     }
+    ///     assert_eq!(pointer, flat.len());
+    /// }
+    /// ```
+    ///
+    /// Truncated input is reported as an error rather than a panic, however short it is:
+    ///
+    /// ```
+    /// use vsf::vsf::{parse, VsfType};
+    ///
+    /// for value in [
+    ///     VsfType::af6((0..300).map(|i| i as f64 * 0.5).collect()),
+    ///     VsfType::u7(u128::MAX),
+    ///     VsfType::x("truncate me".to_owned()),
+    ///     VsfType::h(vec![0xAB; 32]),
+    /// ] {
+    ///     let flat = value.flatten().unwrap();
+    ///     for length in 1..flat.len() {
+    ///         assert!(parse(&flat[..length], &mut 0).is_err());
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// An array length whose byte size overflows `usize` is rejected before anything is
+    /// allocated:
+    ///
+    /// ```
+    /// use vsf::vsf::{parse, EncodeNumber};
+    ///
+    /// let mut data = vec![b'a'];
+    /// data.extend_from_slice(&(usize::MAX / 2).encode_number(false));
+    /// data.extend_from_slice(b"u7");
+    /// let error = parse(&data, &mut 0).unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    /// ```
+    ///
+    /// Empty arrays and strings keep their type: the length is zero and no element bytes follow.
+    ///
+    /// ```
+    /// use vsf::vsf::{parse, VsfType};
+    ///
+    /// for value in [
+    ///     VsfType::au4(vec![]),
+    ///     VsfType::as7(vec![]),
+    ///     VsfType::af6(vec![]),
+    ///     VsfType::x(String::new()),
+    /// ] {
+    ///     let flat = value.flatten().unwrap();
+    ///     let mut pointer = 0;
+    ///     assert_eq!(parse(&flat, &mut pointer).unwrap(), value);
+    ///     assert_eq!(pointer, flat.len());
+    /// }
+    /// ```
+    ///
+    /// Errors keep their kind and report the offset of the byte that could not be decoded, where
+    /// `pointer` is also left. A bad type marker is reported at the marker, a bad size or
+    /// element type at that byte, invalid UTF-8 at its first invalid byte, and data that runs
+    /// out at the field that is missing:
+    ///
+    /// ```
+    /// use vsf::vsf::{parse, parse_all, VsfType};
+    ///
+    /// let mut data = VsfType::x("ok".to_owned()).flatten().unwrap();
+    /// let corrupted = data.len();
+    /// data.append(&mut VsfType::u4(7).flatten().unwrap());
+    /// data[corrupted] = 0x5A;
+    /// let error = parse_all(&data).unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     format!("at byte {}: Invalid type marker 0x5A!", corrupted)
+    /// );
+    ///
+    /// // The width digit of a u4, one byte into the value
+    /// data[corrupted] = b'u';
+    /// data[corrupted + 1] = b'9';
+    /// let error = parse_all(&data).unwrap_err();
+    /// assert!(error.to_string().starts_with(&format!("at byte {}:", corrupted + 1)));
+    ///
+    /// // The stray byte inside a string, not where the string starts
+    /// let text = [b'x', b'3', 5, b'a', b'b', 0xFF, b'c', b'd'];
+    /// let mut pointer = 0;
+    /// let error = parse(&text, &mut pointer).unwrap_err();
+    /// assert!(error.to_string().starts_with("at byte 5:"));
+    /// assert_eq!(pointer, 5);
+    /// ```
     pub fn parse(data: &[u8], pointer: &mut usize) -> Result<VsfType, std::io::Error> {
+        parse_value(data, pointer).map_err(|error| {
+            std::io::Error::new(error.kind(), format!("at byte {}: {}", *pointer, error))
+        })
+    }
+
+    /// Steps `pointer` back onto the selector byte just consumed, so `parse` reports the
+    /// error at that byte.
+    fn rewind(pointer: &mut usize, error: std::io::Error) -> Result<VsfType, std::io::Error> {
+        *pointer -= 1;
+        Err(error)
+    }
+
+    fn parse_value(data: &[u8], pointer: &mut usize) -> Result<VsfType, std::io::Error> {
         if *pointer >= data.len() {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Pointer out of bounds!",
-            ));
+            return Err(std::io::Error::other("Pointer out of bounds!"));
         }
 
         let type_byte = data[*pointer];
         *pointer += 1;
         match type_byte {
             b'u' => {
+                ensure(data, *pointer, 1)?;
                 let size_byte = data[*pointer];
                 *pointer += 1;
                 match size_byte {
                     0 => Ok(VsfType::u0(false)),
                     255 => Ok(VsfType::u0(true)),
                     b'0' => {
+                        ensure(data, *pointer, 1)?;
                         let value = data[*pointer];
                         *pointer += 1;
                         match value {
                             0 => Ok(VsfType::u0(false)),
                             255 => Ok(VsfType::u0(true)),
-                            _ => Err(std::io::Error::new(
-                                std::io::ErrorKind::Other,
-                                "Invalid boolean value!",
-                            )),
+                            _ => rewind(pointer, std::io::Error::other("Invalid boolean value!")),
                         }
                     }
                     b'1' => {
-                        let value = (data[*pointer] & 0b11000000) >> 6;
-                        *pointer += 1;
-                        Ok(VsfType::u(value as usize))
+                        let value = decode_packed(data, pointer, 2)?;
+                        Ok(VsfType::u1(value))
                     }
                     b'2' => {
-                        let value = (data[*pointer] & 0b11110000) >> 4;
-                        *pointer += 1;
-                        Ok(VsfType::u(value as usize))
+                        let value = decode_packed(data, pointer, 4)?;
+                        Ok(VsfType::u2(value))
                     }
                     b'3' => {
+                        ensure(data, *pointer, 1)?;
                         let value = data[*pointer];
                         *pointer += 1;
                         Ok(VsfType::u3(value))
                     }
                     b'4' => {
+                        ensure(data, *pointer, 2)?;
                         let value = u16::from_be_bytes([data[*pointer], data[*pointer + 1]]);
                         *pointer += 2;
                         Ok(VsfType::u4(value))
                     }
                     b'5' => {
+                        ensure(data, *pointer, 4)?;
                         let value = u32::from_be_bytes([
                             data[*pointer],
                             data[*pointer + 1],
@@ -698,6 +1706,7 @@ pub mod vsf {
                         Ok(VsfType::u5(value))
                     }
                     b'6' => {
+                        ensure(data, *pointer, 8)?;
                         let value = u64::from_be_bytes([
                             data[*pointer],
                             data[*pointer + 1],
@@ -712,6 +1721,7 @@ pub mod vsf {
                         Ok(VsfType::u6(value))
                     }
                     b'7' => {
+                        ensure(data, *pointer, 16)?;
                         let value = u128::from_be_bytes([
                             data[*pointer],
                             data[*pointer + 1],
@@ -733,39 +1743,39 @@ pub mod vsf {
                         *pointer += 16;
                         Ok(VsfType::u7(value))
                     }
-                    _ => {
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            "Invalid unsigned integer type!",
-                        ))
-                    }
+                    _ => rewind(
+                        pointer,
+                        std::io::Error::other("Invalid unsigned integer type!"),
+                    ),
                 }
             }
             b's' => {
+                ensure(data, *pointer, 1)?;
                 let size_byte = data[*pointer];
                 *pointer += 1;
                 match size_byte {
                     b'1' => {
-                        let value = (data[*pointer] & 0b11000000) >> 6;
-                        *pointer += 1;
+                        let value = decode_packed(data, pointer, 2)?;
                         Ok(VsfType::s(value as isize))
                     }
                     b'2' => {
-                        let value = (data[*pointer] & 0b11110000) >> 4;
-                        *pointer += 1;
+                        let value = decode_packed(data, pointer, 4)?;
                         Ok(VsfType::s(value as isize))
                     }
                     b'3' => {
+                        ensure(data, *pointer, 1)?;
                         let value = data[*pointer] as i8;
                         *pointer += 1;
                         Ok(VsfType::s3(value))
                     }
                     b'4' => {
+                        ensure(data, *pointer, 2)?;
                         let value = i16::from_be_bytes([data[*pointer], data[*pointer + 1]]);
                         *pointer += 2;
                         Ok(VsfType::s4(value))
                     }
                     b'5' => {
+                        ensure(data, *pointer, 4)?;
                         let value = i32::from_be_bytes([
                             data[*pointer],
                             data[*pointer + 1],
@@ -776,6 +1786,7 @@ pub mod vsf {
                         Ok(VsfType::s5(value))
                     }
                     b'6' => {
+                        ensure(data, *pointer, 8)?;
                         let value = i64::from_be_bytes([
                             data[*pointer],
                             data[*pointer + 1],
@@ -790,6 +1801,7 @@ pub mod vsf {
                         Ok(VsfType::s6(value))
                     }
                     b'7' => {
+                        ensure(data, *pointer, 16)?;
                         let value = i128::from_be_bytes([
                             data[*pointer],
                             data[*pointer + 1],
@@ -811,19 +1823,19 @@ pub mod vsf {
                         *pointer += 16;
                         Ok(VsfType::s7(value))
                     }
-                    _ => {
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            "Invalid signed integer type!",
-                        ))
-                    }
+                    _ => rewind(
+                        pointer,
+                        std::io::Error::other("Invalid signed integer type!"),
+                    ),
                 }
             }
             b'f' => {
+                ensure(data, *pointer, 1)?;
                 let size_byte = data[*pointer];
                 *pointer += 1;
                 match size_byte {
                     b'5' => {
+                        ensure(data, *pointer, 4)?;
                         let value = f32::from_bits(u32::from_be_bytes([
                             data[*pointer],
                             data[*pointer + 1],
@@ -834,6 +1846,7 @@ pub mod vsf {
                         Ok(VsfType::f5(value))
                     }
                     b'6' => {
+                        ensure(data, *pointer, 8)?;
                         let value = f64::from_bits(u64::from_be_bytes([
                             data[*pointer],
                             data[*pointer + 1],
@@ -847,24 +1860,38 @@ pub mod vsf {
                         *pointer += 8;
                         Ok(VsfType::f6(value))
                     }
-                    _ => {
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            "Invalid floating point type",
-                        ))
-                    }
+                    _ => rewind(
+                        pointer,
+                        std::io::Error::other("Invalid floating point type"),
+                    ),
                 }
             }
             b'a' => {
                 let length = decode_usize(data, pointer)?;
+                ensure(data, *pointer, 1)?;
                 let array_type = data[*pointer];
                 *pointer += 1;
                 match array_type {
                     b'u' => {
+                        ensure(data, *pointer, 1)?;
                         let element_size = data[*pointer];
                         *pointer += 1;
                         match element_size {
+                            b'0' => {
+                                let byte_length = length.div_ceil(8);
+                                ensure(data, *pointer, byte_length)?;
+                                let mut bits = crate::bitio::BitReader::new(
+                                    &data[*pointer..*pointer + byte_length],
+                                );
+                                let mut values = Vec::with_capacity(length);
+                                for _ in 0..length {
+                                    values.push(bits.read_bits(1)? == 1);
+                                }
+                                *pointer += byte_length;
+                                Ok(VsfType::au0(values))
+                            }
                             b'3' => {
+                                ensure_elements(data, *pointer, length, 1)?;
                                 let mut values = Vec::with_capacity(length);
                                 for _ in 0..length {
                                     values.push(data[*pointer]);
@@ -873,6 +1900,7 @@ pub mod vsf {
                                 Ok(VsfType::au3(values))
                             }
                             b'4' => {
+                                ensure_elements(data, *pointer, length, 2)?;
                                 let mut values = Vec::with_capacity(length);
                                 for _ in 0..length {
                                     let value =
@@ -883,6 +1911,7 @@ pub mod vsf {
                                 Ok(VsfType::au4(values))
                             }
                             b'5' => {
+                                ensure_elements(data, *pointer, length, 4)?;
                                 let mut values = Vec::with_capacity(length);
                                 for _ in 0..length {
                                     let value = u32::from_be_bytes([
@@ -897,6 +1926,7 @@ pub mod vsf {
                                 Ok(VsfType::au5(values))
                             }
                             b'6' => {
+                                ensure_elements(data, *pointer, length, 8)?;
                                 let mut values = Vec::with_capacity(length);
                                 for _ in 0..length {
                                     let value = u64::from_be_bytes([
@@ -915,6 +1945,7 @@ pub mod vsf {
                                 Ok(VsfType::au6(values))
                             }
                             b'7' => {
+                                ensure_elements(data, *pointer, length, 16)?;
                                 let mut values = Vec::with_capacity(length);
                                 for _ in 0..length {
                                     let value = u128::from_be_bytes([
@@ -940,19 +1971,19 @@ pub mod vsf {
                                 }
                                 Ok(VsfType::au7(values))
                             }
-                            _ => {
-                                return Err(std::io::Error::new(
-                                    std::io::ErrorKind::Other,
-                                    "Invalid unsigned integer array type!",
-                                ))
-                            }
+                            _ => rewind(
+                                pointer,
+                                std::io::Error::other("Invalid unsigned integer array type!"),
+                            ),
                         }
                     }
                     b's' => {
+                        ensure(data, *pointer, 1)?;
                         let element_size = data[*pointer];
                         *pointer += 1;
                         match element_size {
                             b'3' => {
+                                ensure_elements(data, *pointer, length, 1)?;
                                 let mut values = Vec::with_capacity(length);
                                 for _ in 0..length {
                                     values.push(data[*pointer] as i8);
@@ -961,6 +1992,7 @@ pub mod vsf {
                                 Ok(VsfType::as3(values))
                             }
                             b'4' => {
+                                ensure_elements(data, *pointer, length, 2)?;
                                 let mut values = Vec::with_capacity(length);
                                 for _ in 0..length {
                                     let value =
@@ -971,6 +2003,7 @@ pub mod vsf {
                                 Ok(VsfType::as4(values))
                             }
                             b'5' => {
+                                ensure_elements(data, *pointer, length, 4)?;
                                 let mut values = Vec::with_capacity(length);
                                 for _ in 0..length {
                                     let value = i32::from_be_bytes([
@@ -985,6 +2018,7 @@ pub mod vsf {
                                 Ok(VsfType::as5(values))
                             }
                             b'6' => {
+                                ensure_elements(data, *pointer, length, 8)?;
                                 let mut values = Vec::with_capacity(length);
                                 for _ in 0..length {
                                     let value = i64::from_be_bytes([
@@ -1003,6 +2037,7 @@ pub mod vsf {
                                 Ok(VsfType::as6(values))
                             }
                             b'7' => {
+                                ensure_elements(data, *pointer, length, 16)?;
                                 let mut values = Vec::with_capacity(length);
                                 for _ in 0..length {
                                     let value = i128::from_be_bytes([
@@ -1028,19 +2063,19 @@ pub mod vsf {
                                 }
                                 Ok(VsfType::as7(values))
                             }
-                            _ => {
-                                return Err(std::io::Error::new(
-                                    std::io::ErrorKind::Other,
-                                    "Invalid signed integer type!",
-                                ))
-                            }
+                            _ => rewind(
+                                pointer,
+                                std::io::Error::other("Invalid signed integer type!"),
+                            ),
                         }
                     }
                     b'f' => {
+                        ensure(data, *pointer, 1)?;
                         let element_size = data[*pointer];
                         *pointer += 1;
                         match element_size {
                             b'5' => {
+                                ensure_elements(data, *pointer, length, 4)?;
                                 let mut values = Vec::with_capacity(length);
                                 for _ in 0..length {
                                     let value = f32::from_bits(u32::from_be_bytes([
@@ -1055,6 +2090,7 @@ pub mod vsf {
                                 Ok(VsfType::af5(values))
                             }
                             b'6' => {
+                                ensure_elements(data, *pointer, length, 8)?;
                                 let mut values = Vec::with_capacity(length);
                                 for _ in 0..length {
                                     let value = f64::from_bits(u64::from_be_bytes([
@@ -1072,27 +2108,83 @@ pub mod vsf {
                                 }
                                 Ok(VsfType::af6(values))
                             }
-                            _ => {
-                                return Err(std::io::Error::new(
-                                    std::io::ErrorKind::Other,
-                                    "Invalid floating point array type!",
-                                ))
-                            }
+                            _ => rewind(
+                                pointer,
+                                std::io::Error::other("Invalid floating point array type!"),
+                            ),
                         }
                     }
-                    _ => {
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            "Invalid array type",
-                        ))
+                    b'i' => {
+                        ensure(data, *pointer, 1)?;
+                        let element_size = data[*pointer];
+                        *pointer += 1;
+                        match element_size {
+                            b'6' => {
+                                ensure_elements(data, *pointer, length, 8)?;
+                                let mut values = Vec::with_capacity(length);
+                                for _ in 0..length {
+                                    let re = f32::from_bits(u32::from_be_bytes([
+                                        data[*pointer],
+                                        data[*pointer + 1],
+                                        data[*pointer + 2],
+                                        data[*pointer + 3],
+                                    ]));
+                                    let im = f32::from_bits(u32::from_be_bytes([
+                                        data[*pointer + 4],
+                                        data[*pointer + 5],
+                                        data[*pointer + 6],
+                                        data[*pointer + 7],
+                                    ]));
+                                    *pointer += 8;
+                                    values.push(Complex { re, im });
+                                }
+                                Ok(VsfType::ai6(values))
+                            }
+                            b'7' => {
+                                ensure_elements(data, *pointer, length, 16)?;
+                                let mut values = Vec::with_capacity(length);
+                                for _ in 0..length {
+                                    let re = f64::from_bits(u64::from_be_bytes([
+                                        data[*pointer],
+                                        data[*pointer + 1],
+                                        data[*pointer + 2],
+                                        data[*pointer + 3],
+                                        data[*pointer + 4],
+                                        data[*pointer + 5],
+                                        data[*pointer + 6],
+                                        data[*pointer + 7],
+                                    ]));
+                                    let im = f64::from_bits(u64::from_be_bytes([
+                                        data[*pointer + 8],
+                                        data[*pointer + 9],
+                                        data[*pointer + 10],
+                                        data[*pointer + 11],
+                                        data[*pointer + 12],
+                                        data[*pointer + 13],
+                                        data[*pointer + 14],
+                                        data[*pointer + 15],
+                                    ]));
+                                    *pointer += 16;
+                                    values.push(Complex { re, im });
+                                }
+                                Ok(VsfType::ai7(values))
+                            }
+                            _ => rewind(
+                                pointer,
+                                std::io::Error::other("Invalid complex array type!"),
+                            ),
+                        }
                     }
+                    _ => rewind(pointer, std::io::Error::other("Invalid array type")),
                 }
             }
             b'i' => {
+                ensure(data, *pointer, 1)?;
                 let element_size = data[*pointer];
                 *pointer += 1;
                 match element_size {
                     b'6' => {
+                        ensure(data, *pointer, 4)?;
                         let re = f32::from_bits(u32::from_be_bytes([
                             data[*pointer],
                             data[*pointer + 1],
@@ -1100,6 +2192,7 @@ pub mod vsf {
                             data[*pointer + 3],
                         ]));
                         *pointer += 4;
+                        ensure(data, *pointer, 4)?;
                         let im = f32::from_bits(u32::from_be_bytes([
                             data[*pointer],
                             data[*pointer + 1],
@@ -1110,6 +2203,7 @@ pub mod vsf {
                         Ok(VsfType::i6(Complex { re, im }))
                     }
                     b'7' => {
+                        ensure(data, *pointer, 8)?;
                         let re = f64::from_bits(u64::from_be_bytes([
                             data[*pointer],
                             data[*pointer + 1],
@@ -1121,6 +2215,7 @@ pub mod vsf {
                             data[*pointer + 7],
                         ]));
                         *pointer += 8;
+                        ensure(data, *pointer, 8)?;
                         let im = f64::from_bits(u64::from_be_bytes([
                             data[*pointer],
                             data[*pointer + 1],
@@ -1134,27 +2229,44 @@ pub mod vsf {
                         *pointer += 8;
                         Ok(VsfType::i7(Complex { re, im }))
                     }
-                    _ => {
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            "Invalid complex number type!",
-                        ))
-                    }
+                    _ => rewind(
+                        pointer,
+                        std::io::Error::other("Invalid complex number type!"),
+                    ),
                 }
             }
             b'x' => {
                 let length = decode_usize(data, pointer)?;
-                let value = String::from_utf8(data[*pointer..*pointer + length].to_vec()).map_err(
-                    |_| {
-                        std::io::Error::new(
+                ensure(data, *pointer, length)?;
+                let value = match String::from_utf8(data[*pointer..*pointer + length].to_vec()) {
+                    Ok(value) => value,
+                    Err(error) => {
+                        *pointer += error.utf8_error().valid_up_to();
+                        return Err(std::io::Error::new(
                             std::io::ErrorKind::InvalidData,
                             "Invalid UTF-8 string!",
-                        )
-                    },
-                )?;
+                        ));
+                    }
+                };
                 *pointer += length;
                 Ok(VsfType::x(value))
             }
+            b'm' => {
+                let marker = decode_usize(data, pointer)?;
+                Ok(VsfType::m(marker))
+            }
+            b'r' => {
+                let marker = decode_usize(data, pointer)?;
+                Ok(VsfType::r(marker))
+            }
+            b'k' => {
+                let keyframe = decode_usize(data, pointer)?;
+                Ok(VsfType::k(keyframe))
+            }
+            b'e' => {
+                let frame = decode_usize(data, pointer)?;
+                Ok(VsfType::e(frame))
+            }
             b'z' => {
                 let version = decode_usize(data, pointer)?;
                 Ok(VsfType::z(version))
@@ -1165,14 +2277,17 @@ pub mod vsf {
             }
             b'l' => {
                 let length = decode_usize(data, pointer)?;
-                let value = String::from_utf8(data[*pointer..*pointer + length].to_vec()).map_err(
-                    |_| {
-                        std::io::Error::new(
+                ensure(data, *pointer, length)?;
+                let value = match String::from_utf8(data[*pointer..*pointer + length].to_vec()) {
+                    Ok(value) => value,
+                    Err(error) => {
+                        *pointer += error.utf8_error().valid_up_to();
+                        return Err(std::io::Error::new(
                             std::io::ErrorKind::InvalidData,
                             "Label is not valid UTF-8!",
-                        )
-                    },
-                )?;
+                        ));
+                    }
+                };
                 *pointer += length;
                 Ok(VsfType::l(value))
             }
@@ -1190,9 +2305,17 @@ pub mod vsf {
             }
             b'd' => {
                 let length = decode_usize(data, pointer)?;
-                let value = String::from_utf8(data[*pointer..*pointer + length].to_vec()).map_err(
-                    |_| std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid data name!"),
-                )?;
+                ensure(data, *pointer, length)?;
+                let value = match String::from_utf8(data[*pointer..*pointer + length].to_vec()) {
+                    Ok(value) => value,
+                    Err(error) => {
+                        *pointer += error.utf8_error().valid_up_to();
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            "Invalid data name!",
+                        ));
+                    }
+                };
                 *pointer += length;
                 Ok(VsfType::d(value))
             }
@@ -1200,12 +2323,12 @@ pub mod vsf {
             b'g' => {
                 let mut signature_length = decode_usize(data, pointer)?;
                 if signature_length % 8 != 0 {
-                    return Err(std::io::Error::new(
-                        std::io::ErrorKind::Other,
+                    return Err(std::io::Error::other(
                         "Signature length does not land on a byte boundary!",
                     ));
                 }
                 signature_length /= 8;
+                ensure(data, *pointer, signature_length)?;
                 let value = data[*pointer..*pointer + signature_length].to_vec();
                 *pointer += signature_length;
                 Ok(VsfType::g(value))
@@ -1213,53 +2336,591 @@ pub mod vsf {
             b'h' => {
                 let mut hash_length = decode_usize(data, pointer)?;
                 if hash_length % 8 != 0 {
-                    return Err(std::io::Error::new(
-                        std::io::ErrorKind::Other,
+                    return Err(std::io::Error::other(
                         "Hash length does not land on a byte boundary!",
                     ));
                 }
                 hash_length /= 8;
+                ensure(data, *pointer, hash_length)?;
                 let value = data[*pointer..*pointer + hash_length].to_vec();
                 *pointer += hash_length;
                 Ok(VsfType::h(value))
             }
 
+            _ => rewind(
+                pointer,
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Invalid type marker 0x{:02X}!", type_byte),
+                ),
+            ),
+        }
+    }
+    /// Reverses the byte order of every `element_size`-byte element in `buffer`, in place.
+    ///
+    /// Turns a run of big-endian elements into little-endian ones and back again.
+    ///
+    /// ```
+    /// use vsf::vsf::swap_element_bytes;
+    ///
+    /// for element_size in [2, 4, 8] {
+    ///     let original: Vec<u8> = (0..32).collect();
+    ///     let mut buffer = original.clone();
+    ///     swap_element_bytes(&mut buffer, element_size).unwrap();
+    ///     assert_eq!(buffer[0], original[element_size - 1]);
+    ///     assert_eq!(buffer[element_size - 1], original[0]);
+    ///     swap_element_bytes(&mut buffer, element_size).unwrap();
+    ///     assert_eq!(buffer, original);
+    /// }
+    ///
+    /// assert!(swap_element_bytes(&mut [0u8; 6], 4).is_err());
+    /// ```
+    pub fn swap_element_bytes(
+        buffer: &mut [u8],
+        element_size: usize,
+    ) -> Result<(), std::io::Error> {
+        if element_size == 0 || !buffer.len().is_multiple_of(element_size) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "{} bytes is not a whole number of {}-byte elements!",
+                    buffer.len(),
+                    element_size
+                ),
+            ));
+        }
+        for element in buffer.chunks_exact_mut(element_size) {
+            element.reverse();
+        }
+        Ok(())
+    }
+
+    /// Type bytes `parse` understands; `parse_skippable` treats any other letter as a newer type.
+    const KNOWN_MARKERS: &[u8] = b"abcdefghiklmorsuxyz";
+
+    /// Like `parse`, but steps over values of types this reader does not know yet.
+    ///
+    /// Types added in later format versions are written as their marker, a byte length in the
+    /// usual size-digit form, and that many payload bytes. An older reader can then skip them:
+    /// for an unknown marker this reads the length, moves `pointer` past the payload and
+    /// returns `None`. Known types parse exactly as `parse` would.
+    ///
+    /// Only letters can be new type markers. Any other byte, including the structural
+    /// delimiters `( ) < > [ ] :`, fails with `InvalidData` just as it does in `parse`, rather
+    /// than being mistaken for a value and throwing the reader out of step.
+    ///
+    /// ```
+    /// use vsf::vsf::{parse_skippable, VsfType};
+    ///
+    /// let mut data = VsfType::u3(7).flatten().unwrap();
+    /// data.extend_from_slice(&[b'q', b'3', 3, 0xAA, 0xBB, 0xCC]); // from a future version
+    /// data.append(&mut VsfType::x("after".to_owned()).flatten().unwrap());
+    ///
+    /// let mut pointer = 0;
+    /// assert_eq!(parse_skippable(&data, &mut pointer).unwrap(), Some(VsfType::u3(7)));
+    /// assert_eq!(parse_skippable(&data, &mut pointer).unwrap(), None);
+    /// assert_eq!(
+    ///     parse_skippable(&data, &mut pointer).unwrap(),
+    ///     Some(VsfType::x("after".to_owned()))
+    /// );
+    /// assert_eq!(pointer, data.len());
+    ///
+    /// let stray = [b'>', b'3', 1, 0];
+    /// let error = parse_skippable(&stray, &mut 0).unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    /// ```
+    pub fn parse_skippable(
+        data: &[u8],
+        pointer: &mut usize,
+    ) -> Result<Option<VsfType>, std::io::Error> {
+        ensure(data, *pointer, 1)?;
+        let marker = data[*pointer];
+        if KNOWN_MARKERS.contains(&marker) || !marker.is_ascii_alphabetic() {
+            return parse(data, pointer).map(Some);
+        }
+        let mut skip = *pointer + 1;
+        let length = decode_usize(data, &mut skip)?;
+        ensure(data, skip, length)?;
+        *pointer = skip + length;
+        Ok(None)
+    }
+
+    /// Like `parse`, but rejects encodings that are not the one canonical form of their value,
+    /// so signed or hashed bytes cannot be swapped for an equivalent spelling.
+    ///
+    /// The parsed value is flattened again with [`VsfType::flatten_canonical`] and must come
+    /// out byte for byte the same as the input. That refuses integers, lengths and counts
+    /// written wider than needed, packed `u1`/`u2` and `s1`/`s2` values (canonically 8 bits
+    /// wide), NaNs other than the quiet NaN, and booleans in the longer
+    /// `u` `0` `0x00`/`0xFF` form (canonically they are `u` followed by `0x00` or `0xFF`, which
+    /// is what `flatten` writes).
+    ///
+    /// ```
+    /// use vsf::vsf::{parse, parse_strict, VsfType};
+    ///
+    /// let canonical = VsfType::u0(true).flatten().unwrap();
+    /// assert_eq!(canonical, [b'u', 0xFF]);
+    /// assert_eq!(parse_strict(&canonical, &mut 0).unwrap(), VsfType::u0(true));
+    ///
+    /// let alternate = [b'u', b'0', 0xFF];
+    /// assert_eq!(parse(&alternate, &mut 0).unwrap(), VsfType::u0(true));
+    /// assert!(parse_strict(&alternate, &mut 0).is_err());
+    ///
+    /// let wide = VsfType::u6(5).flatten().unwrap();
+    /// assert!(parse_strict(&wide, &mut 0).is_err());
+    /// let narrow = VsfType::u3(5).flatten().unwrap();
+    /// assert_eq!(parse_strict(&narrow, &mut 0).unwrap(), VsfType::u3(5));
+    ///
+    /// // A 3 character string whose length is spelled in two bytes
+    /// let padded_length = [b'x', b'4', 0, 3, b'a', b'b', b'c'];
+    /// assert!(parse(&padded_length, &mut 0).is_ok());
+    /// let error = parse_strict(&padded_length, &mut 0).unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    /// ```
+    pub fn parse_strict(data: &[u8], pointer: &mut usize) -> Result<VsfType, std::io::Error> {
+        let start = *pointer;
+        let mut end = start;
+        let value = parse(data, &mut end)?;
+        if value.flatten_canonical()? != data[start..end] {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("at byte {}: Non-canonical encoding!", start),
+            ));
+        }
+        *pointer = end;
+        Ok(value)
+    }
+
+    /// Parses consecutive values until `data` is used up.
+    pub fn parse_all(data: &[u8]) -> Result<Vec<VsfType>, std::io::Error> {
+        let mut values = Vec::new();
+        let mut pointer = 0;
+        while pointer < data.len() {
+            values.push(parse(data, &mut pointer)?);
+        }
+        Ok(values)
+    }
+
+    /// Parses consecutive values like `parse_all`, handing each one to `visitor` as soon as it
+    /// is read instead of collecting them, so memory use stays flat however many values there
+    /// are. Returning `ControlFlow::Break` from the visitor stops parsing early.
+    ///
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use vsf::vsf::{parse_visit, VsfType};
+    ///
+    /// let mut data = Vec::new();
+    /// for reading in 0..1000u16 {
+    ///     data.append(&mut VsfType::u4(reading).flatten().unwrap());
+    /// }
+    ///
+    /// let mut count = 0;
+    /// parse_visit(&data, |_| {
+    ///     count += 1;
+    ///     ControlFlow::Continue(())
+    /// })
+    /// .unwrap();
+    /// assert_eq!(count, 1000);
+    ///
+    /// let mut seen = Vec::new();
+    /// parse_visit(&data, |value| {
+    ///     seen.push(value);
+    ///     if seen.len() == 3 {
+    ///         ControlFlow::Break(())
+    ///     } else {
+    ///         ControlFlow::Continue(())
+    ///     }
+    /// })
+    /// .unwrap();
+    /// assert_eq!(seen, [VsfType::u4(0), VsfType::u4(1), VsfType::u4(2)]);
+    /// ```
+    pub fn parse_visit<F: FnMut(VsfType) -> std::ops::ControlFlow<()>>(
+        data: &[u8],
+        mut visitor: F,
+    ) -> Result<(), std::io::Error> {
+        let mut pointer = 0;
+        while pointer < data.len() {
+            if visitor(parse(data, &mut pointer)?).is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads an `au3` array without copying it, returning the elements as a slice of `data`.
+    ///
+    /// Single bytes have no byte order, so the stored elements already are the array.
+    ///
+    /// ```
+    /// use vsf::vsf::{parse_au3_borrowed, VsfType};
+    ///
+    /// let mut data = VsfType::u3(1).flatten().unwrap();
+    /// data.append(&mut VsfType::au3((0..=255).collect()).flatten().unwrap());
+    ///
+    /// let mut pointer = 3;
+    /// let pixels = parse_au3_borrowed(&data, &mut pointer).unwrap();
+    /// assert_eq!(pixels.len(), 256);
+    /// assert_eq!(pointer, data.len());
+    /// assert_eq!(pixels.as_ptr(), data[data.len() - 256..].as_ptr());
+    ///
+    /// assert!(parse_au3_borrowed(&data, &mut 0).is_err());
+    /// ```
+    pub fn parse_au3_borrowed<'a>(
+        data: &'a [u8],
+        pointer: &mut usize,
+    ) -> Result<&'a [u8], std::io::Error> {
+        ensure(data, *pointer, 1)?;
+        if data[*pointer] != b'a' {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Not an array!",
+            ));
+        }
+        let mut start = *pointer + 1;
+        let length = decode_usize(data, &mut start)?;
+        ensure(data, start, 2)?;
+        if &data[start..start + 2] != b"u3" {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Not an au3 array!",
+            ));
+        }
+        start += 2;
+        ensure(data, start, length)?;
+        *pointer = start + length;
+        Ok(&data[start..start + length])
+    }
+
+    /// Caps on what `parse_with_limits` may allocate for untrusted input.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ParseLimits {
+        pub max_total_bytes: usize, // Bytes allocated across every value parsed
+        pub max_elements: usize,    // Elements in any one array
+        pub max_string_len: usize,  // Bytes in any one string
+    }
+
+    /// Parses consecutive values like `parse_all`, refusing any value that would take the
+    /// allocations past `limits`.
+    ///
+    /// Each value's length is read and checked before the value itself is parsed, so an
+    /// oversized array or string fails with `InvalidData` without being allocated.
+    ///
+    /// ```
+    /// use vsf::vsf::{parse_with_limits, ParseLimits, VsfType};
+    ///
+    /// let limits = ParseLimits {
+    ///     max_total_bytes: 4096,
+    ///     max_elements: 1000,
+    ///     max_string_len: 64,
+    /// };
+    /// let mut data = VsfType::x("patient".to_owned()).flatten().unwrap();
+    /// data.append(&mut VsfType::au4(vec![0; 500]).flatten().unwrap());
+    /// assert_eq!(parse_with_limits(&data, limits).unwrap().len(), 2);
+    ///
+    /// data.append(&mut VsfType::au6(vec![0; 600]).flatten().unwrap());
+    /// let error = parse_with_limits(&data, limits).unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    ///
+    /// let long = VsfType::x("x".repeat(65)).flatten().unwrap();
+    /// assert!(parse_with_limits(&long, limits).is_err());
+    /// ```
+    pub fn parse_with_limits(
+        data: &[u8],
+        limits: ParseLimits,
+    ) -> Result<Vec<VsfType>, std::io::Error> {
+        let mut values = Vec::new();
+        let mut pointer = 0;
+        let mut total_bytes: usize = 0;
+        while pointer < data.len() {
+            let mut peek = pointer + 1;
+            match data[pointer] {
+                b'a' => {
+                    let length = decode_usize(data, &mut peek)?;
+                    ensure(data, peek, 2)?;
+                    let element_size = match (data[peek], data[peek + 1]) {
+                        (b'u', b'0') | (_, b'3') => 1,
+                        (_, b'4') => 2,
+                        (_, b'5') => 4,
+                        (b'i', b'6') => 8,
+                        (b'i', b'7') => 16,
+                        (_, b'6') => 8,
+                        (_, b'7') => 16,
+                        _ => 0,
+                    };
+                    if length > limits.max_elements {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!(
+                                "Array of {} elements exceeds the limit of {}!",
+                                length, limits.max_elements
+                            ),
+                        ));
+                    }
+                    total_bytes = total_bytes.saturating_add(length.saturating_mul(element_size));
+                }
+                b'x' | b'd' | b'l' => {
+                    let length = decode_usize(data, &mut peek)?;
+                    if length > limits.max_string_len {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!(
+                                "String of {} bytes exceeds the limit of {}!",
+                                length, limits.max_string_len
+                            ),
+                        ));
+                    }
+                    total_bytes = total_bytes.saturating_add(length);
+                }
+                b'h' | b'g' => {
+                    total_bytes = total_bytes.saturating_add(decode_usize(data, &mut peek)? / 8);
+                }
+                _ => {}
+            }
+            if total_bytes > limits.max_total_bytes {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "Parsing would allocate more than {} bytes!",
+                        limits.max_total_bytes
+                    ),
+                ));
+            }
+            values.push(parse(data, &mut pointer)?);
+        }
+        Ok(values)
+    }
+
+    /// Entry point for fuzzing: accepts any bytes and returns an error, never a panic.
+    ///
+    /// Buffers that start with the `RÅ<` magic have their header checked first and the values
+    /// after it parsed, anything else is parsed as a plain run of values. Every read is
+    /// bounds-checked and array lengths are checked against the remaining bytes before any
+    /// allocation, so hostile lengths fail fast instead of exhausting memory.
+    ///
+    /// ```
+    /// use vsf::vsf::{parse_fuzz, EncodeNumber};
+    ///
+    /// // An au6 array claiming 2^40 elements, backed by only 10 bytes
+    /// let mut data = vec![b'a'];
+    /// data.extend_from_slice(&(1usize << 40).encode_number(false));
+    /// data.extend_from_slice(b"u6");
+    /// data.extend_from_slice(&[0u8; 10]);
+    /// assert!(parse_fuzz(&data).is_err());
+    ///
+    /// // A string length of 2^64 + 3 must not wrap around to 3
+    /// let mut data = b"x7".to_vec();
+    /// data.extend_from_slice(&((1u128 << 64) + 3).to_be_bytes());
+    /// data.extend_from_slice(b"abc");
+    /// let error = parse_fuzz(&data).unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    /// ```
+    pub fn parse_fuzz(data: &[u8]) -> Result<Vec<VsfType>, std::io::Error> {
+        if data.starts_with(crate::file_format::MAGIC) {
+            let header = crate::file_format::parse_header(data)?;
+            parse_all(&data[header.length / 8..])
+        } else {
+            parse_all(data)
+        }
+    }
+
+    /// Renders every value in `data` on its own line, for inspecting a file by eye.
+    ///
+    /// Structural bytes (the `RÅ<` magic, `(`, `)`, `>`, `[`, `]` and `:`) get a line of their
+    /// own and indent what they enclose. Parsing stops at the first error, which is reported
+    /// on the final line together with the byte offset it happened at.
+    ///
+    /// ```
+    /// use vsf::vsf::{dump, VsfType};
+    ///
+    /// let mut data = VsfType::d("samples".to_owned()).flatten().unwrap();
+    /// data.append(&mut VsfType::au4((0..1000).collect()).flatten().unwrap());
+    /// assert_eq!(
+    ///     dump(&data),
+    ///     "d \"samples\"\nau4 [0, 1, 2, 3, 4, 5, 6, 7, …] (1000 elements)\n"
+    /// );
+    /// ```
+    pub fn dump(data: &[u8]) -> String {
+        let mut out = String::new();
+        let mut depth: usize = 0;
+        let mut pointer = 0;
+        if data.starts_with(crate::file_format::MAGIC) {
+            out.push_str("RÅ<\n");
+            pointer = crate::file_format::MAGIC.len();
+            depth = 1;
+            // The header length is written inclusively, which plain `parse` would misread
+            if data.get(pointer) == Some(&b'b') {
+                let mut peek = pointer + 1;
+                if let Ok(length) = decode_usize_inclusive(data, &mut peek) {
+                    out.push_str(&format!("  {}\n", VsfType::b(length)));
+                    pointer = peek;
+                }
+            }
+        }
+        while pointer < data.len() {
+            let structural = data[pointer];
+            if matches!(structural, b')' | b'>' | b']') {
+                depth = depth.saturating_sub(1);
+            }
+            out.push_str(&"  ".repeat(depth));
+            match structural {
+                b'(' | b')' | b'<' | b'>' | b'[' | b']' | b':' => {
+                    out.push(structural as char);
+                    pointer += 1;
+                    if matches!(structural, b'(' | b'<' | b'[') {
+                        depth += 1;
+                    }
+                }
+                _ => match parse(data, &mut pointer) {
+                    Ok(value) => out.push_str(&value.to_string()),
+                    Err(error) => {
+                        out.push_str(&format!("error {}\n", error));
+                        return out;
+                    }
+                },
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Fails with `UnexpectedEof` unless `count` more bytes are available at `pointer`.
+    fn ensure(data: &[u8], pointer: usize, count: usize) -> Result<(), std::io::Error> {
+        match pointer.checked_add(count) {
+            Some(end) if end <= data.len() => Ok(()),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Unexpected end of data!",
+            )),
+        }
+    }
+
+    /// Reads a `u1`/`u2` style value held in the top `bits` bits of one byte.
+    ///
+    /// The padding bits below it must be zero, so every value has exactly one encoding.
+    fn decode_packed(data: &[u8], pointer: &mut usize, bits: u32) -> Result<u8, std::io::Error> {
+        ensure(data, *pointer, 1)?;
+        let byte = data[*pointer];
+        if byte << bits != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Nonzero padding bits!",
+            ));
+        }
+        *pointer += 1;
+        Ok(byte >> (8 - bits))
+    }
+
+    /// Checks that `length` elements of `element_size` bytes are all present before anything is
+    /// allocated for them, so a corrupt array length can't request an absurd allocation.
+    fn ensure_elements(
+        data: &[u8],
+        pointer: usize,
+        length: usize,
+        element_size: usize,
+    ) -> Result<(), std::io::Error> {
+        match length.checked_mul(element_size) {
+            Some(total) => ensure(data, pointer, total),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Array length overflows!",
+            )),
+        }
+    }
+
+    /// Reads a length written with `encode_number(true)`, taking off the size digit and value
+    /// bytes it counted, so encoding and then decoding gives back the original value.
+    ///
+    /// The header length that follows the `RÅ<` magic is written this way, and
+    /// [`crate::file_format::parse_header`] reads it back with this function.
+    ///
+    /// ```
+    /// use vsf::vsf::{decode_usize_inclusive, EncodeNumber};
+    ///
+    /// for value in [
+    ///     0,
+    ///     253,
+    ///     254,
+    ///     65532,
+    ///     65533,
+    ///     4_294_967_290,
+    ///     4_294_967_291,
+    ///     usize::MAX - 9,
+    ///     usize::MAX,
+    /// ] {
+    ///     let flat = value.encode_number(true);
+    ///     let mut pointer = 0;
+    ///     assert_eq!(decode_usize_inclusive(&flat, &mut pointer).unwrap(), value);
+    ///     assert_eq!(pointer, flat.len());
+    /// }
+    ///
+    /// // Smaller than the bytes it claims to count
+    /// assert!(decode_usize_inclusive(&[b'3', 1], &mut 0).is_err());
+    /// ```
+    pub fn decode_usize_inclusive(
+        data: &[u8],
+        pointer: &mut usize,
+    ) -> Result<usize, std::io::Error> {
+        ensure(data, *pointer, 1)?;
+        let (size, adder) = match data[*pointer] {
+            b'3' => (1, 2),
+            b'4' => (2, 3),
+            b'5' => (4, 5),
+            b'6' => (8, 9),
+            b'7' => (16, 17),
             _ => {
                 return Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("Invalid type identifier '{}'", type_byte as char),
+                    std::io::ErrorKind::InvalidData,
+                    "Invalid inclusive usize encoding!",
                 ))
             }
-        }
+        };
+        ensure(data, *pointer + 1, size)?;
+        let value = data[*pointer + 1..*pointer + 1 + size]
+            .iter()
+            .fold(0u128, |value, byte| (value << 8) | *byte as u128);
+        let value = value
+            .checked_sub(adder)
+            .and_then(|value| usize::try_from(value).ok())
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Inclusive usize is out of range!",
+                )
+            })?;
+        *pointer += 1 + size;
+        Ok(value)
     }
+
     fn decode_usize(data: &[u8], pointer: &mut usize) -> Result<usize, std::io::Error> {
+        ensure(data, *pointer, 1)?;
         match data[*pointer] {
             b'1' => {
                 *pointer += 1;
-                let value = (data[*pointer] & 0b11000000) >> 6;
-                *pointer += 1;
+                let value = decode_packed(data, pointer, 2)?;
                 Ok(value as usize)
             }
             b'2' => {
                 *pointer += 1;
-                let value = (data[*pointer] & 0b11110000) >> 4;
-                *pointer += 1;
+                let value = decode_packed(data, pointer, 4)?;
                 Ok(value as usize)
             }
             b'3' => {
                 *pointer += 1;
+                ensure(data, *pointer, 1)?;
                 let value = data[*pointer] as usize;
                 *pointer += 1;
                 Ok(value)
             }
             b'4' => {
                 *pointer += 1;
+                ensure(data, *pointer, 2)?;
                 let value = u16::from_be_bytes([data[*pointer], data[*pointer + 1]]) as usize;
                 *pointer += 2;
                 Ok(value)
             }
             b'5' => {
                 *pointer += 1;
+                ensure(data, *pointer, 4)?;
                 let value = u32::from_be_bytes([
                     data[*pointer],
                     data[*pointer + 1],
@@ -1271,6 +2932,7 @@ pub mod vsf {
             }
             b'6' => {
                 *pointer += 1;
+                ensure(data, *pointer, 8)?;
                 let value = u64::from_be_bytes([
                     data[*pointer],
                     data[*pointer + 1],
@@ -1280,12 +2942,16 @@ pub mod vsf {
                     data[*pointer + 5],
                     data[*pointer + 6],
                     data[*pointer + 7],
-                ]) as usize;
+                ]);
+                let value = usize::try_from(value).map_err(|_| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, "Usize is out of range!")
+                })?;
                 *pointer += 8;
                 Ok(value)
             }
             b'7' => {
                 *pointer += 1;
+                ensure(data, *pointer, 16)?;
                 let value = u128::from_be_bytes([
                     data[*pointer],
                     data[*pointer + 1],
@@ -1303,7 +2969,10 @@ pub mod vsf {
                     data[*pointer + 13],
                     data[*pointer + 14],
                     data[*pointer + 15],
-                ]) as usize;
+                ]);
+                let value = usize::try_from(value).map_err(|_| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, "Usize is out of range!")
+                })?;
                 *pointer += 16;
                 Ok(value)
             }
@@ -1314,3 +2983,10 @@ pub mod vsf {
         }
     }
 }
+
+pub mod bitio;
+pub mod file_format;
+#[cfg(feature = "serde_json")]
+pub mod interop;
+pub mod log;
+pub mod verification;
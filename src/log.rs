@@ -0,0 +1,172 @@
+//! Append-only event logs made of independent VSF records.
+//!
+//! Each record is laid out as:
+//!
+//! ```text
+//! b(length in bits) values... u5(crc32 of the values)
+//! ```
+//!
+//! The leading `b` lets a reader step from record to record without scanning the bodies,
+//! and the trailing CRC lets every record be checked on its own.
+
+use crate::vsf::{parse, parse_all, VsfType};
+use std::io::{Read, Write};
+
+/// Appends records to a log.
+///
+/// # Example
+///
+/// ```
+/// use vsf::log::{LogReader, LogWriter};
+/// use vsf::vsf::VsfType;
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let mut writer = LogWriter::new(Vec::new());
+/// writer.append(&[VsfType::l("boot".to_owned())])?;
+/// writer.append(&[VsfType::l("baud".to_owned()), VsfType::u5(115200)])?;
+/// writer.append(&[VsfType::l("shutdown".to_owned())])?;
+/// let log = writer.into_inner();
+///
+/// let records = LogReader::new(&log[..]).collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(records.len(), 3);
+/// assert_eq!(records[1], [VsfType::l("baud".to_owned()), VsfType::u5(115200)]);
+///
+/// // A record cut short by a crash is reported, not dropped
+/// let mut reader = LogReader::new(&log[..log.len() - 3]);
+/// assert!(reader.next().unwrap().is_ok());
+/// assert!(reader.next().unwrap().is_ok());
+/// let error = reader.next().unwrap().unwrap_err();
+/// assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+/// assert!(reader.next().is_none());
+/// # Ok(())
+/// # }
+/// ```
+pub struct LogWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> LogWriter<W> {
+    /// Wraps `writer`, which should already be positioned at the end of the log (for a file,
+    /// open it with `append(true)`).
+    pub fn new(writer: W) -> Self {
+        LogWriter { writer }
+    }
+
+    /// Writes `values` as one record.
+    pub fn append(&mut self, values: &[VsfType]) -> Result<(), std::io::Error> {
+        let mut body = Vec::new();
+        for value in values {
+            body.append(&mut value.flatten()?);
+        }
+        let mut record = VsfType::b(body.len() * 8).flatten()?;
+        record.extend_from_slice(&body);
+        record.append(&mut VsfType::u5(crc32fast::hash(&body)).flatten()?);
+        self.writer.write_all(&record)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads records back from a log, in the order they were written.
+///
+/// Yields an `UnexpectedEof` error for a record that was only partly written, and
+/// `InvalidData` for one whose CRC does not match. Reading stops after the first error.
+pub struct LogReader<R: Read> {
+    reader: R,
+    failed: bool,
+}
+
+impl<R: Read> LogReader<R> {
+    pub fn new(reader: R) -> Self {
+        LogReader {
+            reader,
+            failed: false,
+        }
+    }
+
+    fn read_record(&mut self, marker: u8) -> Result<Vec<VsfType>, std::io::Error> {
+        let mut prefix = vec![marker, 0];
+        self.read_exact(&mut prefix[1..])?;
+        let size = match prefix[1] {
+            b'3' => 1,
+            b'4' => 2,
+            b'5' => 4,
+            b'6' => 8,
+            b'7' => 16,
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Invalid log record length!",
+                ))
+            }
+        };
+        prefix.resize(2 + size, 0);
+        self.read_exact(&mut prefix[2..])?;
+        let length = match parse(&prefix, &mut 0)? {
+            VsfType::b(length) if length.is_multiple_of(8) => length / 8,
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Log record does not start with a byte length!",
+                ))
+            }
+        };
+
+        let mut body = Vec::new();
+        let read = (&mut self.reader)
+            .take(length as u64)
+            .read_to_end(&mut body)?;
+        if read < length {
+            return Err(incomplete());
+        }
+        let mut crc = [0u8; 6];
+        self.read_exact(&mut crc)?;
+        match parse(&crc, &mut 0)? {
+            VsfType::u5(crc) if crc == crc32fast::hash(&body) => parse_all(&body),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Log record CRC mismatch!",
+            )),
+        }
+    }
+
+    fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), std::io::Error> {
+        self.reader.read_exact(buffer).map_err(|error| {
+            if error.kind() == std::io::ErrorKind::UnexpectedEof {
+                incomplete()
+            } else {
+                error
+            }
+        })
+    }
+}
+
+impl<R: Read> Iterator for LogReader<R> {
+    type Item = Result<Vec<VsfType>, std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let mut marker = [0u8];
+        let record = loop {
+            match self.reader.read(&mut marker) {
+                Ok(0) => return None,
+                Ok(_) => break self.read_record(marker[0]),
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(error) => break Err(error),
+            }
+        };
+        self.failed = record.is_err();
+        Some(record)
+    }
+}
+
+fn incomplete() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::UnexpectedEof,
+        "Log record is incomplete!",
+    )
+}
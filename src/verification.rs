@@ -0,0 +1,286 @@
+//! Hashing and signing of VSF data.
+//!
+//! A whole-file signature, written by [`crate::file_format::VsfBuilder::sign`], does not
+//! cover the file's bytes as laid out. It covers a canonical form rebuilt from the parsed
+//! header: the version, backward version, section count, metadata and CRC flag, then for
+//! each section except the signature itself, in label order, its label, its offset past the
+//! end of the header, its length, its count if any, and its body. The signature section is
+//! left out entirely, so adding it to the header changes nothing that was signed.
+//!
+//! A verifier must rebuild the same canonical bytes from the file rather than slicing a byte
+//! range out of it; [`verify_file`] does exactly that. [`sign_ed25519`] and [`verify_ed25519`]
+//! are the lower-level primitives, which sign and check whatever bytes they are given.
+
+use crate::vsf::{parse, VsfType};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use std::io::Read;
+
+/// Incremental BLAKE3 hasher for sections too large to hold in memory at once.
+///
+/// # Example
+///
+/// ```
+/// use vsf::verification::HashBuilder;
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let raster: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+///
+/// let mut chunked = HashBuilder::new();
+/// for chunk in raster.chunks(999) {
+///     chunked.update(chunk);
+/// }
+/// let mut single = HashBuilder::new();
+/// single.update(&raster);
+/// let mut streamed = HashBuilder::new();
+/// streamed.update_reader(&raster[..])?;
+///
+/// let chunked = chunked.finalize().flatten()?;
+/// assert_eq!(chunked, single.finalize().flatten()?);
+/// assert_eq!(chunked, streamed.finalize().flatten()?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct HashBuilder {
+    hasher: blake3::Hasher,
+}
+
+impl HashBuilder {
+    pub fn new() -> Self {
+        HashBuilder {
+            hasher: blake3::Hasher::new(),
+        }
+    }
+
+    /// Feeds the next chunk of data into the hash.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+    }
+
+    /// Feeds everything `reader` yields into the hash, a buffer at a time.
+    pub fn update_reader<R: Read>(&mut self, mut reader: R) -> Result<(), std::io::Error> {
+        let mut buffer = [0u8; 65536];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(read) => {
+                    self.hasher.update(&buffer[..read]);
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Finishes the hash and returns it as an `h` value, whose length carries the digest size.
+    pub fn finalize(self) -> VsfType {
+        VsfType::h(self.hasher.finalize().as_bytes().to_vec())
+    }
+}
+
+impl Default for HashBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Signs `data` with an Ed25519 secret key and returns the signature as a `g` value.
+///
+/// # Example
+///
+/// ```
+/// use vsf::verification::{sign_ed25519, verify_ed25519};
+/// use vsf::vsf::{parse, VsfType};
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let secret_key = [7u8; 32];
+/// let public_key = ed25519_dalek::SigningKey::from_bytes(&secret_key)
+///     .verifying_key()
+///     .to_bytes();
+///
+/// let mut file = VsfType::x("signed text".to_owned()).flatten()?;
+/// let signed_length = file.len();
+/// file.append(&mut sign_ed25519(&file, &secret_key).flatten()?);
+///
+/// let mut pointer = signed_length;
+/// let signature = match parse(&file, &mut pointer)? {
+///     VsfType::g(signature) => signature,
+///     other => panic!("Unexpected {:?}", other),
+/// };
+/// assert!(verify_ed25519(&file[..signed_length], &signature, &public_key));
+///
+/// file[3] ^= 1;
+/// assert!(!verify_ed25519(&file[..signed_length], &signature, &public_key));
+/// # Ok(())
+/// # }
+/// ```
+pub fn sign_ed25519(data: &[u8], secret_key: &[u8; 32]) -> VsfType {
+    let signing_key = SigningKey::from_bytes(secret_key);
+    VsfType::g(signing_key.sign(data).to_bytes().to_vec())
+}
+
+/// Checks an Ed25519 signature over `data`, which must be exactly the signed byte range.
+pub fn verify_ed25519(data: &[u8], signature: &[u8], public_key: &[u8; 32]) -> bool {
+    let verifying_key = match VerifyingKey::from_bytes(public_key) {
+        Ok(key) => key,
+        Err(_) => return false,
+    };
+    let signature = match Signature::from_slice(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    verifying_key.verify(data, &signature).is_ok()
+}
+
+/// Label of the section that [`crate::file_format::VsfBuilder::sign`] stores the file
+/// signature in.
+pub const SIGNATURE_LABEL: &str = "signature";
+
+/// The bytes a whole-file signature covers.
+///
+/// These are the header's version, backward version, section count, metadata and CRC flag,
+/// followed by `d(label) o(offset) b(length) [c(count)] body` for every section except the
+/// signature itself, in label order. Offsets are taken from the end of the header, so adding the
+/// signature entry to the header leaves them unchanged, but moving any section body does not.
+pub(crate) fn canonical_file(
+    data: &[u8],
+    header: &crate::file_format::VsfHeader,
+) -> Result<Vec<u8>, std::io::Error> {
+    let mut labels: Vec<_> = header
+        .labels
+        .iter()
+        .filter(|definition| definition.label != SIGNATURE_LABEL)
+        .collect();
+    labels.sort_by(|a, b| a.label.cmp(&b.label));
+
+    let mut flat = VsfType::z(header.version).flatten()?;
+    flat.append(&mut VsfType::y(header.backward_version).flatten()?);
+    flat.append(&mut VsfType::c(labels.len()).flatten()?);
+    if let Some(name) = &header.source_name {
+        flat.append(&mut VsfType::l("source_name".to_owned()).flatten()?);
+        flat.append(&mut VsfType::x(name.clone()).flatten()?);
+    }
+    if let Some(content_type) = &header.content_type {
+        flat.append(&mut VsfType::l("content_type".to_owned()).flatten()?);
+        flat.append(&mut VsfType::x(content_type.clone()).flatten()?);
+    }
+    if header.crc {
+        flat.append(&mut VsfType::l("crc".to_owned()).flatten()?);
+        flat.append(&mut VsfType::u0(true).flatten()?);
+    }
+    for definition in labels {
+        let offset = definition
+            .offset
+            .checked_sub(header.length)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Section '{}' starts inside the header!", definition.label),
+                )
+            })?;
+        flat.append(&mut VsfType::d(definition.label.clone()).flatten()?);
+        flat.append(&mut VsfType::o(offset).flatten()?);
+        flat.append(&mut VsfType::b(definition.length).flatten()?);
+        if let Some(count) = definition.count {
+            flat.append(&mut VsfType::c(count).flatten()?);
+        }
+        flat.extend_from_slice(crate::file_format::section_bytes(data, definition)?);
+    }
+    Ok(flat)
+}
+
+/// Checks the signature written by [`crate::file_format::VsfBuilder::sign`] against
+/// `public_key`.
+///
+/// Returns `Ok(false)` when the signature does not match and an error when the file has no
+/// readable signature section.
+pub fn verify_file(data: &[u8], public_key: &[u8; 32]) -> Result<bool, std::io::Error> {
+    let header = crate::file_format::parse_header(data)?;
+    let signature = match parse(
+        crate::file_format::read_section(data, SIGNATURE_LABEL)?,
+        &mut 0,
+    )? {
+        VsfType::g(signature) => signature,
+        other => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Expected a signature, found {:?}!", other),
+            ))
+        }
+    };
+    let signed = canonical_file(data, &header)?;
+    Ok(verify_ed25519(&signed, &signature, public_key))
+}
+
+/// BLAKE3 hash of a file's logical content, for content-addressed storage.
+///
+/// Every value is re-encoded with [`VsfType::flatten_canonical`] before hashing, so files that
+/// differ only in how wide their integers and lengths were written hash the same. For files
+/// with a `RÅ<` header the sections are hashed in label order, each as its label, then `c`
+/// with the number of values it holds, then the values. Section bodies that are raw blobs
+/// rather than VSF values are hashed as their label, `b` with their length, then the bytes as
+/// is, so a blob never hashes like a run of values and no section can run into the next.
+///
+/// # Example
+///
+/// ```
+/// use vsf::file_format::VsfBuilder;
+/// use vsf::verification::content_hash;
+/// use vsf::vsf::VsfType;
+///
+/// # fn main() -> Result<(), std::io::Error> {
+/// let narrow = VsfBuilder::new()
+///     .unboxed_section("count", VsfType::u3(5).flatten()?)
+///     .build()?;
+/// let wide = VsfBuilder::new()
+///     .unboxed_section("count", VsfType::u6(5).flatten()?)
+///     .build()?;
+/// assert_ne!(narrow, wide);
+/// assert_eq!(content_hash(&narrow)?, content_hash(&wide)?);
+///
+/// let other = VsfBuilder::new()
+///     .unboxed_section("count", VsfType::u3(6).flatten()?)
+///     .build()?;
+/// assert_ne!(content_hash(&narrow)?, content_hash(&other)?);
+///
+/// // One section holding what looks like a second label is not two sections
+/// let mut one_body = VsfType::u3(1).flatten()?;
+/// one_body.append(&mut VsfType::d("b".to_owned()).flatten()?);
+/// one_body.append(&mut VsfType::u3(2).flatten()?);
+/// let one = VsfBuilder::new().unboxed_section("a", one_body).build()?;
+/// let two = VsfBuilder::new()
+///     .unboxed_section("a", VsfType::u3(1).flatten()?)
+///     .unboxed_section("b", VsfType::u3(2).flatten()?)
+///     .build()?;
+/// assert_ne!(content_hash(&one)?, content_hash(&two)?);
+/// # Ok(())
+/// # }
+/// ```
+pub fn content_hash(data: &[u8]) -> Result<[u8; 32], std::io::Error> {
+    let mut hasher = blake3::Hasher::new();
+    if data.starts_with(crate::file_format::MAGIC) {
+        let header = crate::file_format::parse_header(data)?;
+        let mut labels: Vec<_> = header.labels.iter().collect();
+        labels.sort_by(|a, b| a.label.cmp(&b.label));
+        for definition in labels {
+            let body = crate::file_format::section_bytes(data, definition)?;
+            hasher.update(&VsfType::d(definition.label.clone()).flatten_canonical()?);
+            match crate::vsf::parse_all(body) {
+                Ok(values) => {
+                    hasher.update(&VsfType::c(values.len()).flatten_canonical()?);
+                    for value in values {
+                        hasher.update(&value.flatten_canonical()?);
+                    }
+                }
+                Err(_) => {
+                    hasher.update(&VsfType::b(body.len() * 8).flatten_canonical()?);
+                    hasher.update(body);
+                }
+            }
+        }
+    } else {
+        for value in crate::vsf::parse_all(data)? {
+            hasher.update(&value.flatten_canonical()?);
+        }
+    }
+    Ok(*hasher.finalize().as_bytes())
+}
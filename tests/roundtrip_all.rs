@@ -0,0 +1,150 @@
+//! Flattens and parses one representative value of every `VsfType` variant.
+//!
+//! A variant that can be written but not read back fails here, so this list doubles as the
+//! definition of what "fully supported" means.
+
+use num_complex::Complex;
+use vsf::vsf::{parse, VsfType};
+
+/// Values paired with what parsing them must give back. Most variants come back as
+/// themselves; the auto-sized `u` and `s` come back as the fixed-width variant they were
+/// written with.
+fn cases() -> Vec<(VsfType, VsfType)> {
+    let same = |value: VsfType| (value.clone(), value);
+    vec![
+        (VsfType::u(5), VsfType::u3(5)),
+        (VsfType::u(70_000), VsfType::u5(70_000)),
+        same(VsfType::u1(3)),
+        same(VsfType::u2(15)),
+        same(VsfType::u3(u8::MAX)),
+        same(VsfType::u4(u16::MAX)),
+        same(VsfType::u5(u32::MAX)),
+        same(VsfType::u6(u64::MAX)),
+        same(VsfType::u7(u128::MAX)),
+        (VsfType::s(5), VsfType::s3(5)),
+        (VsfType::s(-129), VsfType::s4(-129)),
+        (VsfType::s(-32769), VsfType::s5(-32769)),
+        same(VsfType::s3(i8::MIN)),
+        same(VsfType::s4(i16::MIN)),
+        same(VsfType::s5(i32::MIN)),
+        same(VsfType::s6(i64::MIN)),
+        same(VsfType::s7(i128::MIN)),
+        same(VsfType::f5(-1.5)),
+        same(VsfType::f6(std::f64::consts::PI)),
+        same(VsfType::au3(vec![0, 1, u8::MAX])),
+        same(VsfType::au4(vec![0, 1, u16::MAX])),
+        same(VsfType::au5(vec![0, 1, u32::MAX])),
+        same(VsfType::au6(vec![0, 1, u64::MAX])),
+        same(VsfType::au7(vec![0, 1, u128::MAX])),
+        same(VsfType::as3(vec![i8::MIN, 0, i8::MAX])),
+        same(VsfType::as4(vec![i16::MIN, 0, i16::MAX])),
+        same(VsfType::as5(vec![i32::MIN, 0, i32::MAX])),
+        same(VsfType::as6(vec![i64::MIN, 0, i64::MAX])),
+        same(VsfType::as7(vec![i128::MIN, 0, i128::MAX])),
+        same(VsfType::af5(vec![-0.0, 1.25, f32::MAX])),
+        same(VsfType::af6(vec![-0.0, 1.25, f64::MAX])),
+        same(VsfType::i6(Complex::new(1.5, -2.5))),
+        same(VsfType::i7(Complex::new(1.5, -2.5))),
+        same(VsfType::ai6(vec![
+            Complex::new(1.0, 2.0),
+            Complex::new(-3.0, 4.0),
+        ])),
+        same(VsfType::ai7(vec![
+            Complex::new(1.0, 2.0),
+            Complex::new(-3.0, 4.0),
+        ])),
+        same(VsfType::u0(false)),
+        same(VsfType::u0(true)),
+        same(VsfType::au0(vec![
+            true, false, true, true, false, false, true, false, true,
+        ])),
+        same(VsfType::x("Zoë 名前".to_owned())),
+        same(VsfType::d("elevation_meters".to_owned())),
+        same(VsfType::l("thumbnail".to_owned())),
+        same(VsfType::o(1 << 20)),
+        same(VsfType::b(4096)),
+        same(VsfType::c(3)),
+        same(VsfType::z(1)),
+        same(VsfType::y(1)),
+        same(VsfType::m(2)),
+        same(VsfType::r(2)),
+        same(VsfType::k(30)),
+        same(VsfType::e(1800)),
+        same(VsfType::h(vec![0xAB; 32])),
+        same(VsfType::g(vec![0xCD; 64])),
+    ]
+}
+
+#[test]
+fn every_variant_round_trips() {
+    let mut failures = Vec::new();
+    for (value, expected) in cases() {
+        let flat = match value.flatten() {
+            Ok(flat) => flat,
+            Err(error) => {
+                failures.push(format!("{:?}: flatten failed: {}", value, error));
+                continue;
+            }
+        };
+        let mut pointer = 0;
+        match parse(&flat, &mut pointer) {
+            Ok(parsed) if parsed != expected => {
+                failures.push(format!("{:?}: parsed as {:?}", value, parsed))
+            }
+            Ok(_) if pointer != flat.len() => failures.push(format!(
+                "{:?}: parse stopped at byte {} of {}",
+                value,
+                pointer,
+                flat.len()
+            )),
+            Ok(_) => {}
+            Err(error) => failures.push(format!("{:?}: parse failed: {}", value, error)),
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn complex_arrays_keep_real_and_imaginary_parts_apart() {
+    let values: Vec<Complex<f64>> = (0..4)
+        .map(|index| Complex::new(index as f64, index as f64 + 100.0))
+        .collect();
+    let flat = VsfType::ai7(values).flatten().unwrap();
+    match parse(&flat, &mut 0).unwrap() {
+        VsfType::ai7(parsed) => {
+            for (index, value) in parsed.iter().enumerate() {
+                assert_eq!(value.re, index as f64);
+                assert_eq!(value.im, index as f64 + 100.0);
+            }
+        }
+        other => panic!("Unexpected {:?}", other),
+    }
+
+    let values: Vec<Complex<f32>> = (0..4)
+        .map(|index| Complex::new(index as f32, index as f32 + 100.0))
+        .collect();
+    let flat = VsfType::ai6(values).flatten().unwrap();
+    match parse(&flat, &mut 0).unwrap() {
+        VsfType::ai6(parsed) => {
+            for (index, value) in parsed.iter().enumerate() {
+                assert_eq!(value.re, index as f32);
+                assert_eq!(value.im, index as f32 + 100.0);
+            }
+        }
+        other => panic!("Unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn packed_values_reject_nonzero_padding() {
+    for flat in [
+        &[b'u', b'1', 0b1100_0001][..],
+        &[b'u', b'2', 0b1111_1000],
+        &[b's', b'1', 0b0100_0010],
+        &[b's', b'2', 0b0001_0001],
+        &[b'x', b'1', 0b0100_0001, b'a'],
+    ] {
+        let error = parse(flat, &mut 0).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}